@@ -1,21 +1,53 @@
 
 #[cfg(test)]
 mod alpine_user_tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_binary, DepsMut, Addr };
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        from_binary,
+        to_binary,
+        coin,
+        coins,
+        DepsMut,
+        OwnedDeps,
+        Addr,
+        BankMsg,
+        CosmosMsg,
+        ContractInfoResponse,
+        ContractResult,
+        SystemError,
+        SystemResult,
+        Uint128,
+        WasmQuery
+    };
 
     use crate::msg::{
         InstantiateMsg,
         ExecuteMsg,
         QueryMsg,
         MultiUserResponse,
-        AlpineUserResponse
+        AlpineUserResponse,
+        UsernameHistoryResponse,
+        UsernameListingsResponse,
+        UsernameTransferResponse,
+        UsernameExpiryResponse,
+        RenewalFee,
+        RenewalFeesResponse
     };
     use crate::{
         ContractError,
         state::{
             AlpineContract,
-            AlpineUser
+            AlpineUser,
+            UsernameChange,
+            UsernameListing,
+            AcceptedDenom,
+            UnavailableReason,
+            PatternKind,
+            BlockedPattern,
+            USERNAME_CHANGE_COOLDOWN,
+            RELEASED_USERNAME_GRACE_PERIOD,
+            USERNAME_REGISTRATION_PERIOD,
+            EXPIRED_USERNAME_GRACE_PERIOD
         },
         msg::UsernameAvailableResponse
     };
@@ -23,7 +55,14 @@ mod alpine_user_tests {
     // A basic utility function to setup the contract so we don't have to do this every time
     fn setup_contract(deps: DepsMut<'_>) -> AlpineContract<'static> {
         let contract = AlpineContract::default();
-        let msg = InstantiateMsg {};
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 300,
+            fee_collector: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![]
+        };
         let info = mock_info("creator", &[]);
 
         let res = contract.instantiate(deps, mock_env(), info, msg).unwrap();
@@ -31,6 +70,26 @@ mod alpine_user_tests {
         contract
     }
 
+    // The address of a CW20 token contract, which is accepted under its address
+    const CW20_TOKEN: &str = "osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9";
+
+    // A utility function to accept a CW20 token for donations, and have the querier know it as a contract
+    fn accept_cw20_token(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, contract: &AlpineContract) {
+        let accepted_denom = AcceptedDenom {
+            denom: String::from(CW20_TOKEN),
+            symbol: String::from("TOKEN"),
+            decimals: 6,
+            min_amount: Uint128::new(1)
+        };
+        contract.accepted_denoms.save(&mut deps.storage, CW20_TOKEN, &accepted_denom).unwrap();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == CW20_TOKEN => {
+                SystemResult::Ok(ContractResult::Ok(to_binary(&ContractInfoResponse::new(1, "creator")).unwrap()))
+            },
+            _ => SystemResult::Err(SystemError::Unknown {})
+        });
+    }
+
     // Attempt to create a user with an invalid wallet address. Should error out
     #[test]
     fn create_user_invalid_addr() {
@@ -231,7 +290,7 @@ mod alpine_user_tests {
         let msg = QueryMsg::IsUsernameAvailable { username: String::from("alpine_user_2") };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let username_response: UsernameAvailableResponse = from_binary(&res).unwrap();
-        assert_eq!(username_response.is_available, true);
+        assert!(username_response.is_available);
     }
 
     // Check if an registered username is available. Should return false.
@@ -251,7 +310,8 @@ mod alpine_user_tests {
         let msg = QueryMsg::IsUsernameAvailable { username: String::from("alpine_user_1") };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let username_response: UsernameAvailableResponse = from_binary(&res).unwrap();
-        assert_eq!(username_response.is_available, false);
+        assert!(!username_response.is_available);
+        assert_eq!(username_response.reason, Some(UnavailableReason::Taken));
     }
 
     // Check if a username is available. Technically the username is unregistered, but the only difference
@@ -272,7 +332,7 @@ mod alpine_user_tests {
         let msg = QueryMsg::IsUsernameAvailable { username: String::from("ALPINE_USER_1") };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let username_response: UsernameAvailableResponse = from_binary(&res).unwrap();
-        assert_eq!(username_response.is_available, false);
+        assert!(!username_response.is_available);
     }
 
     // Attempt to register a user with a taken username. Should error out.
@@ -304,6 +364,570 @@ mod alpine_user_tests {
         assert_eq!(_res, ContractError::UsernameNotAvailable { username });
     }
 
+    // Attempt to register a username which only differs from a taken one by casing. Should error out.
+    #[test]
+    fn save_username_unavailable_case_insensitive() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let test_user = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            None
+        ).unwrap();
+        let msg = ExecuteMsg::RegisterUser {
+            user: test_user.clone(),
+            username: String::from("Alpine_User_1")
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(test_user.address.as_str(), &[]), msg).unwrap();
+
+        let new_user = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"),
+            None
+        ).unwrap();
+        let username = String::from("alpine_user_1");
+        let msg = ExecuteMsg::RegisterUser {
+            user: new_user.clone(),
+            username: username.clone()
+        };
+        let info = mock_info(new_user.address.as_str(), &[]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameNotAvailable { username });
+
+        // The registered user keeps the casing they chose
+        let msg = QueryMsg::GetUserByName { username: String::from("ALPINE_USER_1") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "Alpine_User_1");
+    }
+
+    // Change a username, then try to change it again and take the old one from another address. Should record the change,
+    // keep the old username reserved for the grace period and enforce the cooldown
+    #[test]
+    fn change_username() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let test_user = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            None
+        ).unwrap();
+        let other_user = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"),
+            None
+        ).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: test_user.clone(), username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(test_user.address.as_str(), &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("alpine_user_2") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(test_user.address.as_str(), &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByAddr { address: test_user.address.clone() }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "alpine_user_2");
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByName { username: String::from("alpine_user_1") }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user, AlpineUser::empty());
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUsernameHistory { address: test_user.address.clone() }).unwrap();
+        let history: UsernameHistoryResponse = from_binary(&res).unwrap();
+        assert_eq!(history.changes, vec![UsernameChange {
+            old_username: String::from("alpine_user_1"),
+            new_username: String::from("alpine_user_2"),
+            changed_at: mock_env().block.time
+        }]);
+
+        // Nobody else can take the old username during the grace period
+        let msg = QueryMsg::IsUsernameAvailable { username: String::from("alpine_user_1") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let availability: UsernameAvailableResponse = from_binary(&res).unwrap();
+        assert!(!availability.is_available);
+        let msg = ExecuteMsg::RegisterUser { user: other_user.clone(), username: String::from("alpine_user_1") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(other_user.address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameNotAvailable { username: String::from("alpine_user_1") });
+
+        // The username can't be changed again until the cooldown has passed
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("alpine_user_1") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(test_user.address.as_str(), &[]), msg).unwrap_err();
+        let available_at = mock_env().block.time.plus_seconds(USERNAME_CHANGE_COOLDOWN);
+        assert_eq!(res, ContractError::UsernameChangeCooldown { available_at });
+
+        // Once the grace period is over, anybody can take the old username
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(RELEASED_USERNAME_GRACE_PERIOD);
+        let msg = ExecuteMsg::RegisterUser { user: other_user.clone(), username: String::from("Alpine_User_1") };
+        contract.execute(deps.as_mut(), env, mock_info(other_user.address.as_str(), &[]), msg).unwrap();
+    }
+
+    // Change a username back during its grace period. Should give the previous owner their old username back
+    #[test]
+    fn change_username_reclaim() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let test_user = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            None
+        ).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: test_user.clone(), username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(test_user.address.as_str(), &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("alpine_user_2") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(test_user.address.as_str(), &[]), msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(USERNAME_CHANGE_COOLDOWN);
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("Alpine_User_1") };
+        contract.execute(deps.as_mut(), env, mock_info(test_user.address.as_str(), &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByName { username: String::from("alpine_user_1") }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.address, test_user.address);
+        assert_eq!(user.user.username, "Alpine_User_1");
+
+        // Unregistered addresses have no username to change
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("alpine_user_3") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9", &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::UserNotFound { user: String::from("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9") });
+    }
+
+    // List a username and buy it from an unregistered address. Should only take prices in native denoms, pay the seller
+    // the price minus the fee, and move the username to the buyer
+    #[test]
+    fn buy_username() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let accepted_denom = AcceptedDenom {
+            denom: String::from("uosmo"),
+            symbol: String::from("OSMO"),
+            decimals: 6,
+            min_amount: Uint128::new(1)
+        };
+        contract.accepted_denoms.save(&mut deps.storage, "uosmo", &accepted_denom).unwrap();
+
+        let seller = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"), None).unwrap();
+        let buyer = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: seller.clone(), username: String::from("ab") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(seller.address.as_str(), &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ListUsername { price: coin(1000, "mars") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(seller.address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::DenomNotAccepted { denom: String::from("mars") });
+        // Buyers pay with native funds, so usernames can't be priced in CW20 tokens
+        accept_cw20_token(&mut deps, &contract);
+        let msg = ExecuteMsg::ListUsername { price: coin(1000, CW20_TOKEN) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(seller.address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::NativeDenomRequired { denom: String::from(CW20_TOKEN) });
+        let msg = ExecuteMsg::ListUsername { price: coin(1000, "uosmo") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(seller.address.as_str(), &[]), msg).unwrap();
+
+        let msg = QueryMsg::GetUsernameListings { start_after: None, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let listings: UsernameListingsResponse = from_binary(&res).unwrap();
+        assert_eq!(listings.listings, vec![UsernameListing {
+            username: String::from("ab"),
+            seller: seller.address.clone(),
+            price: coin(1000, "uosmo")
+        }]);
+
+        let msg = ExecuteMsg::BuyUsername { username: String::from("AB") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(buyer.address.as_str(), &coins(900, "uosmo")), msg).unwrap_err();
+        assert_eq!(res, ContractError::IncorrectPayment { expected: String::from("1000uosmo") });
+
+        let msg = ExecuteMsg::BuyUsername { username: String::from("AB") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(buyer.address.as_str(), &coins(1000, "uosmo")), msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: seller.address.to_string(), amount: coins(970, "uosmo") }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            amount: coins(30, "uosmo")
+        }));
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByName { username: String::from("ab") }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.address, buyer.address);
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByAddr { address: seller.address.clone() }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "");
+
+        // The listing is gone once the username is sold
+        let msg = ExecuteMsg::BuyUsername { username: String::from("ab") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(seller.address.as_str(), &coins(1000, "uosmo")), msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameNotListed { username: String::from("ab") });
+    }
+
+    // List usernames and let one of them expire. Should skip the expired username's listing and refuse to list it again
+    #[test]
+    fn list_expired_username() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let accepted_denom = AcceptedDenom {
+            denom: String::from("uosmo"),
+            symbol: String::from("OSMO"),
+            decimals: 6,
+            min_amount: Uint128::new(1)
+        };
+        contract.accepted_denoms.save(&mut deps.storage, "uosmo", &accepted_denom).unwrap();
+
+        let expiring = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"), None).unwrap();
+        let seller = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"), None).unwrap();
+        for (user, username) in [(&expiring, "ab"), (&seller, "cd")].iter() {
+            let msg = ExecuteMsg::RegisterUser { user: (*user).clone(), username: String::from(*username) };
+            contract.execute(deps.as_mut(), mock_env(), mock_info(user.address.as_str(), &[]), msg).unwrap();
+            let msg = ExecuteMsg::ListUsername { price: coin(1000, "uosmo") };
+            contract.execute(deps.as_mut(), mock_env(), mock_info(user.address.as_str(), &[]), msg).unwrap();
+        }
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(10);
+        contract.username_expiries.save(&mut deps.storage, String::from("ab"), &env.block.time).unwrap();
+
+        // The first page only has the expired listing, so it comes back empty with a cursor to the next one
+        let msg = QueryMsg::GetUsernameListings { start_after: None, limit: Some(1) };
+        let res = contract.query(deps.as_ref(), env.clone(), msg).unwrap();
+        let listings: UsernameListingsResponse = from_binary(&res).unwrap();
+        assert_eq!(listings.listings, vec![]);
+        assert_eq!(listings.next_cursor, Some(String::from("ab")));
+
+        let msg = QueryMsg::GetUsernameListings { start_after: listings.next_cursor, limit: Some(1) };
+        let res = contract.query(deps.as_ref(), env.clone(), msg).unwrap();
+        let listings: UsernameListingsResponse = from_binary(&res).unwrap();
+        assert_eq!(listings.listings, vec![UsernameListing {
+            username: String::from("cd"),
+            seller: seller.address.clone(),
+            price: coin(1000, "uosmo")
+        }]);
+        assert_eq!(listings.next_cursor, None);
+
+        let msg = ExecuteMsg::ListUsername { price: coin(500, "uosmo") };
+        let res = contract.execute(deps.as_mut(), env, mock_info(expiring.address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameExpired { username: String::from("ab") });
+    }
+
+    // Offer a username to another address and accept it. Should only move the username once the recipient accepts it
+    #[test]
+    fn transfer_username() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let holder = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"), None).unwrap();
+        let recipient = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: holder.clone(), username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(holder.address.as_str(), &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::TransferUsername { recipient: recipient.address.to_string() };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(holder.address.as_str(), &[]), msg).unwrap();
+
+        let msg = QueryMsg::GetUsernameTransfer { username: String::from("alpine_user_1") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let transfer: UsernameTransferResponse = from_binary(&res).unwrap();
+        assert_eq!(transfer.transfer.unwrap().to, recipient.address);
+
+        // The username still belongs to the holder until it's accepted, and only the recipient can accept it
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByName { username: String::from("alpine_user_1") }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.address, holder.address);
+        let msg = ExecuteMsg::AcceptUsernameTransfer { username: String::from("alpine_user_1") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9", &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::NoPendingUsernameTransfer { username: String::from("alpine_user_1") });
+
+        let msg = ExecuteMsg::AcceptUsernameTransfer { username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(recipient.address.as_str(), &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByAddr { address: recipient.address.clone() }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "alpine_user_1");
+        let msg = QueryMsg::GetUsernameTransfer { username: String::from("alpine_user_1") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let transfer: UsernameTransferResponse = from_binary(&res).unwrap();
+        assert_eq!(transfer.transfer, None);
+    }
+
+    // Register usernames in other scripts once Unicode usernames are enabled. Should normalize them, reject usernames
+    // which mix scripts or look like another user's username, and keep rejecting lookalikes of Unicode usernames after
+    // Unicode usernames are disabled
+    #[test]
+    fn register_unicode_usernames() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let addresses = [
+            "osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh",
+            "osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9",
+            "osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x",
+            "osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9",
+            "osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"
+        ];
+        let users: Vec<AlpineUser> = addresses
+            .iter()
+            .map(|address| AlpineUser::new(deps.as_ref(), Addr::unchecked(*address), None).unwrap())
+            .collect();
+
+        // Usernames are limited to ASCII until Unicode usernames are enabled
+        let msg = ExecuteMsg::RegisterUser { user: users[0].clone(), username: String::from("Дмитрий") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[0].address.as_str(), &[]), msg.clone()).unwrap_err();
+        let enable = ExecuteMsg::SetUnicodeUsernames { enabled: true };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), enable).unwrap();
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[0].address.as_str(), &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByName { username: String::from("дмитрий") }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "Дмитрий");
+
+        // Compatibility forms are normalized, so fullwidth letters are stored as ordinary ones
+        let msg = ExecuteMsg::RegisterUser { user: users[1].clone(), username: String::from("ａｐｅｘ") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[1].address.as_str(), &[]), msg).unwrap();
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByAddr { address: users[1].address.clone() }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "apex");
+
+        // A Cyrillic username which looks like a registered Latin one
+        let msg = ExecuteMsg::RegisterUser { user: users[2].clone(), username: String::from("арех") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(users[2].address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameConfusable { username: String::from("арех"), existing: String::from("apex") });
+        let msg = QueryMsg::IsUsernameAvailable { username: String::from("арех") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let availability: UsernameAvailableResponse = from_binary(&res).unwrap();
+        assert_eq!(availability.reason, Some(UnavailableReason::Confusable { username: String::from("apex") }));
+
+        // A username which mixes Latin and Cyrillic letters
+        let msg = ExecuteMsg::RegisterUser { user: users[3].clone(), username: String::from("pаypal") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(users[3].address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidUsername {
+            username: String::from("pаypal"),
+            reason: String::from("must be written in a single script")
+        });
+
+        // A user can change their username to one which looks like their own
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("арех") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[1].address.as_str(), &[]), msg).unwrap();
+
+        // Lookalike ASCII usernames aren't compared while Unicode usernames are disabled, but every one of them stays
+        // protected from Unicode lookalikes once one of them is removed
+        let msg = ExecuteMsg::RegisterUser { user: users[3].clone(), username: String::from("сосоа") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[3].address.as_str(), &[]), msg).unwrap();
+        let disable = ExecuteMsg::SetUnicodeUsernames { enabled: false };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), disable).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: users[2].clone(), username: String::from("coco1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[2].address.as_str(), &[]), msg).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: users[4].clone(), username: String::from("cocol") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[4].address.as_str(), &[]), msg).unwrap();
+
+        // A Latin username which looks like a registered Cyrillic one is still rejected after Unicode usernames are disabled
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("cocoa") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(users[2].address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameConfusable { username: String::from("cocoa"), existing: String::from("сосоа") });
+
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("alpine_user_3") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(users[2].address.as_str(), &[]), msg).unwrap();
+        let enable = ExecuteMsg::SetUnicodeUsernames { enabled: true };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), enable).unwrap();
+        let msg = QueryMsg::IsUsernameAvailable { username: String::from("сосо1") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let availability: UsernameAvailableResponse = from_binary(&res).unwrap();
+        assert_eq!(availability.reason, Some(UnavailableReason::Confusable { username: String::from("cocol") }));
+    }
+
+    // Register Unicode usernames which look like released, reserved or blocked usernames. Should reject them for the
+    // same reason as the usernames they look like
+    #[test]
+    fn register_unicode_lookalikes() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let renamer = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"), None).unwrap();
+        let other = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"), None).unwrap();
+
+        let enable = ExecuteMsg::SetUnicodeUsernames { enabled: true };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), enable).unwrap();
+        let reserve = ExecuteMsg::ReserveUsernames { usernames: vec![String::from("pepe")] };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), reserve).unwrap();
+        let block = ExecuteMsg::BlockPattern { kind: PatternKind::Substring, pattern: String::from("ace") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), block).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: renamer.clone(), username: String::from("oops") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(renamer.address.as_str(), &[]), msg).unwrap();
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(renamer.address.as_str(), &[]), msg).unwrap();
+
+        let available_at = mock_env().block.time.plus_seconds(RELEASED_USERNAME_GRACE_PERIOD);
+        let lookalikes = [
+            ("рере", UnavailableReason::Reserved),
+            ("расе", UnavailableReason::Blocked { pattern: BlockedPattern { kind: PatternKind::Substring, pattern: String::from("ace") } }),
+            ("оорѕ", UnavailableReason::GracePeriod { available_at })
+        ];
+        for (username, reason) in lookalikes.iter() {
+            let msg = QueryMsg::IsUsernameAvailable { username: String::from(*username) };
+            let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+            let availability: UsernameAvailableResponse = from_binary(&res).unwrap();
+            assert_eq!(availability.reason, Some(reason.clone()));
+            let msg = ExecuteMsg::RegisterUser { user: other.clone(), username: String::from(*username) };
+            contract.execute(deps.as_mut(), mock_env(), mock_info(other.address.as_str(), &[]), msg).unwrap_err();
+        }
+
+        // Once the grace period is over, the released username's lookalikes can be registered
+        let mut env = mock_env();
+        env.block.time = available_at;
+        let msg = ExecuteMsg::RegisterUser { user: other.clone(), username: String::from("оорѕ") };
+        contract.execute(deps.as_mut(), env, mock_info(other.address.as_str(), &[]), msg).unwrap();
+    }
+
+    // Renew usernames with fees set by length. Should charge the fee of the shortest length covering the username to
+    // the fee collector, extend the expiry by a registration period, refuse usernames no fee covers, and only take
+    // fees in native denoms
+    #[test]
+    fn renew_username() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let accepted_denom = AcceptedDenom {
+            denom: String::from("uosmo"),
+            symbol: String::from("OSMO"),
+            decimals: 6,
+            min_amount: Uint128::new(1)
+        };
+        contract.accepted_denoms.save(&mut deps.storage, "uosmo", &accepted_denom).unwrap();
+
+        // Only a fee manager can set renewal fees, and they have to be in an accepted denom
+        let msg = ExecuteMsg::SetRenewalFee { length: 3, fee: Some(coin(500, "uosmo")) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9", &[]), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetRenewalFee { length: 5, fee: Some(coin(100, "mars")) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::DenomNotAccepted { denom: String::from("mars") });
+        accept_cw20_token(&mut deps, &contract);
+        let msg = ExecuteMsg::SetRenewalFee { length: 5, fee: Some(coin(100, CW20_TOKEN)) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::NativeDenomRequired { denom: String::from(CW20_TOKEN) });
+        let msg = ExecuteMsg::SetRenewalFee { length: 5, fee: Some(coin(100, "uosmo")) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetRenewalFees { }).unwrap();
+        let fees: RenewalFeesResponse = from_binary(&res).unwrap();
+        assert_eq!(fees.fees, vec![
+            RenewalFee { length: 3, fee: coin(500, "uosmo") },
+            RenewalFee { length: 5, fee: coin(100, "uosmo") }
+        ]);
+
+        let short = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"), None).unwrap();
+        let long = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: short.clone(), username: String::from("abcd") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(short.address.as_str(), &[]), msg).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: long.clone(), username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(long.address.as_str(), &[]), msg).unwrap();
+
+        let expires_at = mock_env().block.time.plus_seconds(USERNAME_REGISTRATION_PERIOD);
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUsernameExpiry { username: String::from("ABCD") }).unwrap();
+        let expiry: UsernameExpiryResponse = from_binary(&res).unwrap();
+        assert_eq!(expiry, UsernameExpiryResponse {
+            username: String::from("abcd"),
+            expires_at: Some(expires_at),
+            grace_ends_at: Some(expires_at.plus_seconds(EXPIRED_USERNAME_GRACE_PERIOD)),
+            is_expired: false,
+            renewal_fee: Some(coin(100, "uosmo"))
+        });
+
+        // Renewing takes exactly the fee
+        let msg = ExecuteMsg::RenewUsername { };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(short.address.as_str(), &coins(50, "uosmo")), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::IncorrectPayment { expected: String::from("100uosmo") });
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(short.address.as_str(), &coins(100, "uosmo")), msg.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            amount: coins(100, "uosmo")
+        }));
+        let renewed_at = expires_at.plus_seconds(USERNAME_REGISTRATION_PERIOD);
+        assert_eq!(contract.username_expiries.load(&deps.storage, String::from("abcd")).unwrap(), renewed_at);
+
+        // Usernames longer than every fee can't be renewed, even for free
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(long.address.as_str(), &[]), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::RenewalFeeNotSet { username: String::from("alpine_user_1") });
+        assert_eq!(contract.username_expiries.load(&deps.storage, String::from("alpine_user_1")).unwrap(), expires_at);
+        let fee = ExecuteMsg::SetRenewalFee { length: 20, fee: Some(coin(10, "uosmo")) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), fee).unwrap();
+        contract.execute(deps.as_mut(), mock_env(), mock_info(long.address.as_str(), &coins(10, "uosmo")), msg).unwrap();
+        assert_eq!(contract.username_expiries.load(&deps.storage, String::from("alpine_user_1")).unwrap(), renewed_at);
+
+        // Removing a fee leaves the longer lengths to cover shorter usernames
+        let msg = ExecuteMsg::SetRenewalFee { length: 5, fee: None };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(contract.renewal_fee(&deps.storage, "ab").unwrap(), Some(coin(500, "uosmo")));
+        assert_eq!(contract.renewal_fee(&deps.storage, "abcd").unwrap(), Some(coin(10, "uosmo")));
+    }
+
+    // Let a username expire. Should stop it receiving donations, let its holder renew it during the grace period, and
+    // let anybody take it once the grace period is over
+    #[test]
+    fn expired_username() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let accepted_denom = AcceptedDenom {
+            denom: String::from("uosmo"),
+            symbol: String::from("OSMO"),
+            decimals: 6,
+            min_amount: Uint128::new(1)
+        };
+        contract.accepted_denoms.save(&mut deps.storage, "uosmo", &accepted_denom).unwrap();
+        let msg = ExecuteMsg::SetRenewalFee { length: 20, fee: Some(coin(100, "uosmo")) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let holder = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"), None).unwrap();
+        let other = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user: holder.clone(), username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(holder.address.as_str(), &[]), msg).unwrap();
+
+        let expires_at = mock_env().block.time.plus_seconds(USERNAME_REGISTRATION_PERIOD);
+        let mut env = mock_env();
+        env.block.time = expires_at;
+
+        // Expired usernames can't receive donations, and aren't found by name
+        let donation = ExecuteMsg::SendDonation {
+            sender: String::from(""),
+            recipient: String::from("alpine_user_1"),
+            message: String::from("")
+        };
+        let res = contract.execute(deps.as_mut(), env.clone(), mock_info(other.address.as_str(), &coins(100, "uosmo")), donation.clone()).unwrap_err();
+        assert_eq!(res, ContractError::UserNotFound { user: String::from("alpine_user_1") });
+        let res = contract.query(deps.as_ref(), env.clone(), QueryMsg::GetUserByName { username: String::from("alpine_user_1") }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user, AlpineUser::empty());
+
+        // Only the holder can have the username during the grace period
+        let grace_ends_at = expires_at.plus_seconds(EXPIRED_USERNAME_GRACE_PERIOD);
+        let msg = QueryMsg::IsUsernameAvailable { username: String::from("alpine_user_1") };
+        let res = contract.query(deps.as_ref(), env.clone(), msg).unwrap();
+        let availability: UsernameAvailableResponse = from_binary(&res).unwrap();
+        assert_eq!(availability.reason, Some(UnavailableReason::Expired { available_at: grace_ends_at }));
+        let register = ExecuteMsg::RegisterUser { user: other.clone(), username: String::from("alpine_user_1") };
+        let res = contract.execute(deps.as_mut(), env.clone(), mock_info(other.address.as_str(), &[]), register.clone()).unwrap_err();
+        assert_eq!(res, ContractError::UsernameNotAvailable { username: String::from("alpine_user_1") });
+
+        // Renewing during the grace period extends it from when it expired
+        contract.execute(deps.as_mut(), env.clone(), mock_info(holder.address.as_str(), &coins(100, "uosmo")), ExecuteMsg::RenewUsername { }).unwrap();
+        let renewed_at = expires_at.plus_seconds(USERNAME_REGISTRATION_PERIOD);
+        assert_eq!(contract.username_expiries.load(&deps.storage, String::from("alpine_user_1")).unwrap(), renewed_at);
+        contract.execute(deps.as_mut(), env, mock_info(other.address.as_str(), &coins(100, "uosmo")), donation).unwrap();
+
+        // Once the grace period is over, anybody can take the username and the holder is left unregistered
+        let mut env = mock_env();
+        env.block.time = renewed_at.plus_seconds(EXPIRED_USERNAME_GRACE_PERIOD);
+        contract.execute(deps.as_mut(), env.clone(), mock_info(other.address.as_str(), &[]), register).unwrap();
+        let res = contract.query(deps.as_ref(), env.clone(), QueryMsg::GetUserByName { username: String::from("alpine_user_1") }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.address, other.address);
+        let res = contract.query(deps.as_ref(), env.clone(), QueryMsg::GetUserByAddr { address: holder.address.clone() }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "");
+        assert_eq!(
+            contract.username_expiries.load(&deps.storage, String::from("alpine_user_1")).unwrap(),
+            env.block.time.plus_seconds(USERNAME_REGISTRATION_PERIOD)
+        );
+
+        let res = contract.execute(deps.as_mut(), env, mock_info(holder.address.as_str(), &[]), ExecuteMsg::RenewUsername { }).unwrap_err();
+        assert_eq!(res, ContractError::UserNotFound { user: holder.address.to_string() });
+    }
+
     // Attempt to save a user with an unregistered username. Should be successful
     #[test]
     fn save_username_success() {
@@ -355,12 +979,45 @@ mod alpine_user_tests {
         ).unwrap();
         contract.usernames.save(&mut deps.storage, String::from("alpine_user_3"), &new_user).unwrap();
 
-        let msg = QueryMsg::GetAllUsers { };
+        let msg = QueryMsg::GetAllUsers { start_after: None, limit: None };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let users: MultiUserResponse  = from_binary(&res).unwrap();
         assert_eq!(users.users.len(), 3)
     }
 
+    // Page through the saved users. Should pick up after the cursor and stop at the limit
+    #[test]
+    fn get_usernames_paginated(){
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let addresses = [
+            "osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh",
+            "osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9",
+            "osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9"
+        ];
+        for (i, address) in addresses.iter().enumerate() {
+            let test_user = AlpineUser::new(
+                deps.as_ref(),
+                Addr::unchecked(*address),
+                Some(format!("Alpine_User_{}", i + 1))
+            ).unwrap();
+            contract.usernames.save(&mut deps.storage, format!("alpine_user_{}", i + 1), &test_user).unwrap();
+        }
+
+        let msg = QueryMsg::GetAllUsers { start_after: None, limit: Some(2) };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let users: MultiUserResponse = from_binary(&res).unwrap();
+        assert_eq!(users.users.len(), 2);
+        assert_eq!(users.next_cursor, Some(String::from("alpine_user_2")));
+
+        let msg = QueryMsg::GetAllUsers { start_after: users.next_cursor, limit: Some(2) };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let users: MultiUserResponse = from_binary(&res).unwrap();
+        assert_eq!(users.users[0].username, "Alpine_User_3");
+        assert_eq!(users.next_cursor, None);
+    }
+
     // Attempt to register a new user whose username prior to this was empty
     #[test]
     fn change_username_from_anonymous(){
@@ -475,19 +1132,50 @@ mod alpine_user_tests {
 #[cfg(test)]
 mod donation_tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_binary, DepsMut, coins, MessageInfo, Addr};
+    use cosmwasm_std::{from_binary, to_binary, DepsMut, coin, coins, MessageInfo, Addr, Attribute, BankMsg, CosmosMsg, Empty, Timestamp, Uint128, WasmMsg};
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+    use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
 
     use crate::msg::{
         InstantiateMsg,
         ExecuteMsg,
         QueryMsg,
         MultiDonationResponse,
+        DonationResponse,
+        DonationFilter,
+        DonationDirection,
+        UserTotalsResponse,
+        UniqueDonorsResponse,
+        LargestDonationResponse,
+        PlatformTotalsResponse,
+        TopSupporter,
+        TopSupportersResponse,
+        DailyStatsResponse,
+        SimulateDonationResponse,
+        StatementEntry,
+        StatementTotal,
+        StatementResponse,
+        STATEMENT_VERSION,
+        AcceptedDenomsResponse,
+        AcceptedNftContractsResponse,
+        Cw20HookMsg,
+        Cw721HookMsg
     };
     use crate::{
         ContractError,
         state::{
             AlpineContract,
-            AlpineUser
+            AlpineUser,
+            username_key,
+            AcceptedDenom,
+            TokenType,
+            UserTotals,
+            LargestDonation,
+            PlatformTotals,
+            DailyStats,
+            CoinSplit,
+            day_of,
+            SECONDS_PER_DAY
         },
         traits::DonationQuery,
     };
@@ -495,7 +1183,27 @@ mod donation_tests {
     // A utility function to set up a contract
     fn setup_contract(deps: DepsMut<'_>) -> AlpineContract<'static> {
         let contract = AlpineContract::default();
-        let msg = InstantiateMsg {};
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 300,
+            fee_collector: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![
+                AcceptedDenom {
+                    denom: String::from("earth"),
+                    symbol: String::from("EARTH"),
+                    decimals: 6,
+                    min_amount: Uint128::new(1)
+                },
+                AcceptedDenom {
+                    denom: String::from("mars"),
+                    symbol: String::from("MARS"),
+                    decimals: 6,
+                    min_amount: Uint128::new(1)
+                }
+            ]
+        };
         let info = mock_info("creator", &[]);
 
         let res = contract.instantiate(deps, mock_env(), info, msg).unwrap();
@@ -531,7 +1239,7 @@ mod donation_tests {
 
         let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message, 
@@ -564,8 +1272,8 @@ mod donation_tests {
         };
 
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_b.username.clone(), &alpine_user_b).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message, 
@@ -594,8 +1302,8 @@ mod donation_tests {
         ).unwrap();
         let info = mock_info(alpine_user_a.address.as_str(), &coins(0, "earth"));
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_b.username.clone(), &alpine_user_b).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message, 
@@ -627,8 +1335,8 @@ mod donation_tests {
         ).unwrap();
         let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_b.username.clone(), &alpine_user_b).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message, 
@@ -667,10 +1375,10 @@ mod donation_tests {
         ).unwrap();
         let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_b.username.clone(), &alpine_user_b).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_c.username.clone(), &alpine_user_c).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_d.username.clone(), &alpine_user_d).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_c.username), &alpine_user_c).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_d.username), &alpine_user_d).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message.clone(), 
@@ -693,7 +1401,7 @@ mod donation_tests {
         };
         let _res = contract.execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
         
-        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username.clone() };
+        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username.clone(), start_after: None, limit: None };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let sent_donations: MultiDonationResponse = from_binary(&res).unwrap();
         assert_eq!(3, sent_donations.donations.len());
@@ -726,10 +1434,10 @@ mod donation_tests {
         ).unwrap();
         let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_b.username.clone(), &alpine_user_b).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_c.username.clone(), &alpine_user_c).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_d.username.clone(), &alpine_user_d).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_c.username), &alpine_user_c).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_d.username), &alpine_user_d).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message.clone() + "1", 
@@ -752,7 +1460,7 @@ mod donation_tests {
         };
         let _res = contract.execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
         
-        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username.clone() };
+        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username.clone(), start_after: None, limit: None };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let sent_donations: MultiDonationResponse = from_binary(&res).unwrap();
         assert_eq!(donation_message.clone() + "1", sent_donations.donations[0].1.message);
@@ -787,10 +1495,10 @@ mod donation_tests {
             Some(String::from("USER_D"))
         ).unwrap();
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_b.username.clone(), &alpine_user_b).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_c.username.clone(), &alpine_user_c).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_d.username.clone(), &alpine_user_d).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_c.username), &alpine_user_c).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_d.username), &alpine_user_d).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message.clone(), 
@@ -816,7 +1524,7 @@ mod donation_tests {
         let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
         let _res = contract.execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
         
-        let msg = QueryMsg::GetReceivedDonations { recipient: alpine_user_a.username.clone() };
+        let msg = QueryMsg::GetReceivedDonations { recipient: alpine_user_a.username.clone(), start_after: None, limit: None };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let received_donations: MultiDonationResponse = from_binary(&res).unwrap();
         assert_eq!(2, received_donations.donations.len());
@@ -848,10 +1556,10 @@ mod donation_tests {
             Some(String::from("USER_D"))
         ).unwrap();
         let contract = setup_contract(deps.as_mut());
-        contract.usernames.save(&mut deps.storage, alpine_user_a.username.clone(), &alpine_user_a).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_b.username.clone(), &alpine_user_b).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_c.username.clone(), &alpine_user_c).unwrap();
-        contract.usernames.save(&mut deps.storage, alpine_user_d.username.clone(), &alpine_user_d).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_c.username), &alpine_user_c).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_d.username), &alpine_user_d).unwrap();
 
         let msg = ExecuteMsg::SendDonation { 
             message: donation_message.clone() + "1", 
@@ -877,91 +1585,1877 @@ mod donation_tests {
         let info = mock_info(alpine_user_c.address.as_str(), &coins(1000, "earth"));
         let _res = contract.execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
         
-        let msg = QueryMsg::GetReceivedDonations { recipient: alpine_user_a.username.clone() };
+        let msg = QueryMsg::GetReceivedDonations { recipient: alpine_user_a.username.clone(), start_after: None, limit: None };
         let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
         let received_donations: MultiDonationResponse = from_binary(&res).unwrap();
         assert_eq!(donation_message.clone() + "1", received_donations.donations[0].1.message);
         assert_eq!(donation_message.clone() + "2", received_donations.donations[1].1.message);
         assert_eq!(donation_message.clone() + "3", received_donations.donations[2].1.message);
     }
-}
-
-// Define a set of integration tests that use our entry points instead of internal calls
-#[cfg(test)]
-mod integration_tests {
-    use cosmwasm_std::{testing::{mock_dependencies, mock_info, mock_env}, Addr, from_binary};
 
-    use crate::{InstantiateMsg, entry::{instantiate, migrate, query, execute}, MigrateMsg, state::AlpineUser, ExecuteMsg, QueryMsg, msg::MultiUserResponse};
-
-    // Validate that instantiation works from the client's perspective
+    // Attempt to send a donation in a denom that isn't accepted. Should error out
     #[test]
-    fn proper_instantiation() {
+    fn send_donation_unaccepted_denom() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {};
-        let info = mock_info("creator", &[]);
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
 
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        let msg = ExecuteMsg::SendDonation { 
+            message: String::from("henlo :)"), 
+            sender: alpine_user_a.username,
+            recipient: alpine_user_b.username
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &[coin(1000, "earth"), coin(1000, "ibc/DUST")]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::DenomNotAccepted { denom: String::from("ibc/DUST") });
     }
 
-    // Validate that migration works from the client's perspective
+    // Raise the minimum donation for a denom, then attempt to send less than it. Should error out
     #[test]
-    fn proper_migration() {
+    fn send_donation_below_minimum() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {};
-        let info = mock_info("creator", &[]);
-        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let accepted_denom = AcceptedDenom {
+            denom: String::from("earth"),
+            symbol: String::from("EARTH"),
+            decimals: 6,
+            min_amount: Uint128::new(5000)
+        };
+        let msg = ExecuteMsg::SetAcceptedDenom { accepted_denom: accepted_denom.clone() };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
 
-        let msg = MigrateMsg {};
-        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
-        assert_eq!(0, res.messages.len())
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetAcceptedDenoms { }).unwrap();
+        let accepted_denoms: AcceptedDenomsResponse = from_binary(&res).unwrap();
+        assert_eq!(accepted_denoms.denoms[0], accepted_denom);
+        assert_eq!(accepted_denoms.denoms.len(), 2);
+
+        let msg = ExecuteMsg::SendDonation { 
+            message: String::from("henlo :)"), 
+            sender: alpine_user_a.username,
+            recipient: alpine_user_b.username
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::DonationBelowMinimum { denom: String::from("earth"), min_amount: Uint128::new(5000) });
     }
 
-    // Validate that execution works from the client's perspective
+    // Send a CW20 donation through the receive hook. Should pay out with CW20 transfers and record the token
     #[test]
-    fn successful_execute() {
+    fn send_cw20_donation() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {};
-        let info = mock_info("creator", &[]);
-        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-
         let alpine_user_a: AlpineUser = AlpineUser::new(
             deps.as_ref(),
-            Addr::unchecked("osmo1tpsscvhaddf36gjvnyjhtwsyempptupypngxzs"),
-            Some(String::from(""))
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
         ).unwrap();
-        let msg = ExecuteMsg::RegisterUser {
-            user: alpine_user_a.clone(),
-            username: String::from("a_tester")
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let token = Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9");
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let accepted_denom = AcceptedDenom {
+            denom: token.to_string(),
+            symbol: String::from("TOKEN"),
+            decimals: 6,
+            min_amount: Uint128::new(1)
         };
-        let info = mock_info(alpine_user_a.address.as_str(), &[]);
-        
-        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetAcceptedDenom { accepted_denom };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let hook = Cw20HookMsg::SendDonation {
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone(),
+            message: String::from("henlo :)")
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: alpine_user_a.address.to_string(),
+            amount: Uint128::new(1000),
+            msg: to_binary(&hook).unwrap()
+        });
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(token.as_str(), &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: alpine_user_b.address.to_string(),
+                amount: Uint128::new(970)
+            }).unwrap(),
+            funds: vec![]
+        }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+                amount: Uint128::new(30)
+            }).unwrap(),
+            funds: vec![]
+        }));
+
+        let msg = QueryMsg::GetReceivedDonations { recipient: alpine_user_b.username, start_after: None, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let received_donations: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(received_donations.donations[0].1.token_type, TokenType::Cw20 { contract_addr: token });
     }
 
-    // Validate that queries work from the client's perspective
+    // Attempt to send a CW20 donation from a token that isn't accepted. Should error out
     #[test]
-    fn successful_query() {
+    fn send_cw20_donation_unaccepted_token() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {};
-        let info = mock_info("creator", &[]);
-        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-
         let alpine_user_a: AlpineUser = AlpineUser::new(
             deps.as_ref(),
-            Addr::unchecked("osmo1tpsscvhaddf36gjvnyjhtwsyempptupypngxzs"),
-            Some(String::from(""))
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
         ).unwrap();
-        let msg = ExecuteMsg::RegisterUser {
-            user: alpine_user_a.clone(),
-            username: String::from("a_tester")
-        };
-        let info = mock_info(alpine_user_a.address.as_str(), &[]);
-        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
 
-        let msg = QueryMsg::GetAllUsers { };
-        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
-        let users: MultiUserResponse  = from_binary(&res).unwrap();
-        assert_eq!(users.users.len(), 1)
+        let hook = Cw20HookMsg::SendDonation {
+            sender: alpine_user_a.username,
+            recipient: alpine_user_b.username,
+            message: String::from("henlo :)")
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: alpine_user_a.address.to_string(),
+            amount: Uint128::new(1000),
+            msg: to_binary(&hook).unwrap()
+        });
+        let info = mock_info("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9", &[]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::DenomNotAccepted { denom: String::from("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9") });
     }
-}
\ No newline at end of file
+
+    // Send a donation with multiple denoms attached. Every coin should be split and forwarded
+    #[test]
+    fn send_multi_denom_donation() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let msg = ExecuteMsg::SendDonation { 
+            message: String::from("henlo :)"), 
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone()
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &[coin(1000, "earth"), coin(200, "mars")]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: alpine_user_b.address.to_string(),
+            amount: vec![coin(970, "earth"), coin(194, "mars")]
+        }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            amount: vec![coin(30, "earth"), coin(6, "mars")]
+        }));
+        assert!(res.attributes.contains(&Attribute::new("fee", "30earth,6mars")));
+        assert!(res.attributes.contains(&Attribute::new("net_amount", "970earth,194mars")));
+
+        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username, start_after: None, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let sent_donations: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![coin(1000, "earth"), coin(200, "mars")], sent_donations.donations[0].1.amount);
+    }
+
+    // Gift an NFT through the receive hook. Should forward the NFT to the recipient and record the gift
+    #[test]
+    fn send_nft_gift() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let nft_contract = Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9");
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+        let msg = ExecuteMsg::SetAcceptedNftContract { contract_addr: nft_contract.to_string() };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let hook = Cw721HookMsg::SendGift {
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone(),
+            message: String::from("henlo :)")
+        };
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: alpine_user_a.address.to_string(),
+            token_id: String::from("42"),
+            msg: to_binary(&hook).unwrap()
+        });
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(nft_contract.as_str(), &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: nft_contract.to_string(),
+            msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: alpine_user_b.address.to_string(),
+                token_id: String::from("42")
+            }).unwrap(),
+            funds: vec![]
+        }));
+
+        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username, start_after: None, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let sent_donations: MultiDonationResponse = from_binary(&res).unwrap();
+        assert!(sent_donations.donations[0].1.amount.is_empty());
+        assert_eq!(
+            sent_donations.donations[0].1.token_type,
+            TokenType::Cw721 { contract_addr: nft_contract, token_id: String::from("42") }
+        );
+    }
+
+    // Attempt to gift an NFT on behalf of somebody else's username. Should error out
+    #[test]
+    fn send_nft_gift_wrong_sender() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+        contract.accepted_nft_contracts.save(&mut deps.storage, &Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9"), &Empty {}).unwrap();
+
+        let hook = Cw721HookMsg::SendGift {
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username,
+            message: String::from("henlo :)")
+        };
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9"),
+            token_id: String::from("42"),
+            msg: to_binary(&hook).unwrap()
+        });
+        let info = mock_info("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9", &[]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidWalletAddress { address: alpine_user_a.address.to_string() });
+    }
+
+    // Attempt to gift an NFT from a contract which isn't accepted. Should error out, since an unlisted contract could
+    // claim any sender
+    #[test]
+    fn send_nft_gift_unaccepted_contract() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B"))
+        ).unwrap();
+        let nft_contract = Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9");
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        // Only a fee manager can accept NFT contracts
+        let msg = ExecuteMsg::SetAcceptedNftContract { contract_addr: nft_contract.to_string() };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(alpine_user_a.address.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+
+        let hook = Cw721HookMsg::SendGift {
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username,
+            message: String::from("henlo :)")
+        };
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: alpine_user_a.address.to_string(),
+            token_id: String::from("42"),
+            msg: to_binary(&hook).unwrap()
+        });
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(nft_contract.as_str(), &[]), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::NftContractNotAccepted { contract_addr: nft_contract.to_string() });
+
+        // Removing a contract stops its gifts again
+        let accept = ExecuteMsg::SetAcceptedNftContract { contract_addr: nft_contract.to_string() };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), accept).unwrap();
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetAcceptedNftContracts { }).unwrap();
+        let accepted: AcceptedNftContractsResponse = from_binary(&res).unwrap();
+        assert_eq!(accepted.contracts, vec![nft_contract.clone()]);
+        contract.execute(deps.as_mut(), mock_env(), mock_info(nft_contract.as_str(), &[]), msg.clone()).unwrap();
+
+        let remove = ExecuteMsg::RemoveAcceptedNftContract { contract_addr: nft_contract.to_string() };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), remove).unwrap();
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(nft_contract.as_str(), &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::NftContractNotAccepted { contract_addr: nft_contract.to_string() });
+    }
+
+    // Query a donation after the recipient's username changes. Should return the current username and the username at donation time
+    #[test]
+    fn get_donations_resolves_current_username() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let mut alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let msg = ExecuteMsg::SendDonation { 
+            message: String::from("henlo :)"), 
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone()
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
+        contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        alpine_user_b.username = String::from("USER_B_RENAMED");
+        contract.addresses.save(&mut deps.storage, alpine_user_b.address.clone(), &alpine_user_b).unwrap();
+
+        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username, start_after: None, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let sent_donations: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(sent_donations.donations[0].1.recipient, alpine_user_b);
+        assert_eq!(sent_donations.donations[0].1.recipient_username_at_donation, "USER_B");
+    }
+
+    // Get a single donation by the ID emitted when it was sent. Should return the donation, and error out for an unknown ID
+    #[test]
+    fn get_donation_by_id() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let msg = ExecuteMsg::SendDonation { 
+            message: String::from("henlo :)"), 
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone()
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let id: u64 = res.attributes.iter().find(|a| a.key == "id").unwrap().value.parse().unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetDonation { id }).unwrap();
+        let donation: DonationResponse = from_binary(&res).unwrap();
+        assert_eq!(donation.id, id);
+        assert_eq!(donation.donation.message, "henlo :)");
+        assert_eq!(donation.donation.sender_username_at_donation, alpine_user_a.username);
+        assert_eq!(donation.donation.fee_bps, 300);
+        assert_eq!(donation.donation.splits, vec![
+            CoinSplit { gross: coin(1000, "earth"), fee: coin(30, "earth"), net: coin(970, "earth") }
+        ]);
+
+        contract.query(deps.as_ref(), mock_env(), QueryMsg::GetDonation { id: id + 1 }).unwrap_err();
+    }
+
+    // Send a donation of several coins. Should record the fee and net amount of every coin at the fee in force, rounding
+    // the fee down
+    #[test]
+    fn send_donation_records_fee_splits() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B"))
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let msg = ExecuteMsg::SendDonation {
+            message: String::from("henlo :)"),
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone()
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &[coin(1001, "earth"), coin(10, "mars")]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let id: u64 = res.attributes.iter().find(|a| a.key == "id").unwrap().value.parse().unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetDonation { id }).unwrap();
+        let donation: DonationResponse = from_binary(&res).unwrap();
+        assert_eq!(donation.donation.amount, vec![coin(1001, "earth"), coin(10, "mars")]);
+        assert_eq!(donation.donation.fee_bps, 300);
+        assert_eq!(donation.donation.splits, vec![
+            CoinSplit { gross: coin(1001, "earth"), fee: coin(30, "earth"), net: coin(971, "earth") },
+            CoinSplit { gross: coin(10, "mars"), fee: coin(0, "mars"), net: coin(10, "mars") }
+        ]);
+    }
+
+    // List the donations between one sender and one recipient. Should leave out donations to and from anybody else
+    #[test]
+    fn get_donations_between() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let alpine_user_c: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9"),
+            Some(String::from("USER_C")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        for user in [&alpine_user_a, &alpine_user_b, &alpine_user_c] {
+            contract.usernames.save(&mut deps.storage, username_key(&user.username), user).unwrap();
+        }
+
+        let donations = [(&alpine_user_a, &alpine_user_b), (&alpine_user_c, &alpine_user_b), (&alpine_user_a, &alpine_user_c), (&alpine_user_a, &alpine_user_b)];
+        for (sender, recipient) in donations {
+            let msg = ExecuteMsg::SendDonation { 
+                message: String::from("henlo :)"), 
+                sender: sender.username.clone(),
+                recipient: recipient.username.clone()
+            };
+            let info = mock_info(sender.address.as_str(), &coins(1000, "earth"));
+            contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let msg = QueryMsg::GetDonationsBetween {
+            sender: String::from("user_a"),
+            recipient: alpine_user_b.username.clone(),
+            start_after: None,
+            limit: Some(1)
+        };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(page.donations[0].0, 1);
+        assert_eq!(page.next_cursor, Some(1));
+
+        let msg = QueryMsg::GetDonationsBetween {
+            sender: alpine_user_a.username,
+            recipient: alpine_user_b.username,
+            start_after: page.next_cursor,
+            limit: Some(1)
+        };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(page.donations[0].0, 4);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    // Search a recipient's donations by time, denom and amount. Should only return matching donations, page through them,
+    // and only leave out NFT gifts when filtering by denom or amount
+    #[test]
+    fn search_donations() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        const DAY: u64 = 24 * 60 * 60;
+        let now = mock_env().block.time;
+        let donations = [(10 * DAY, coin(1000, "earth")), (3 * DAY, coin(100, "earth")), (2 * DAY, coin(1000, "mars")), (DAY, coin(2000, "earth")), (0, coin(600, "earth"))];
+        for (age, amount) in donations {
+            let msg = ExecuteMsg::SendDonation { 
+                message: String::from("henlo :)"), 
+                sender: alpine_user_a.username.clone(),
+                recipient: alpine_user_b.username.clone()
+            };
+            let mut env = mock_env();
+            env.block.time = now.minus_seconds(age);
+            let info = mock_info(alpine_user_a.address.as_str(), &[amount]);
+            contract.execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+        let nft_contract = Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9");
+        contract.accepted_nft_contracts.save(&mut deps.storage, &nft_contract, &Empty {}).unwrap();
+        let hook = Cw721HookMsg::SendGift {
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone(),
+            message: String::from("henlo :)")
+        };
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: alpine_user_a.address.to_string(),
+            token_id: String::from("42"),
+            msg: to_binary(&hook).unwrap()
+        });
+        let mut env = mock_env();
+        env.block.time = now.minus_seconds(5 * DAY);
+        contract.execute(deps.as_mut(), env, mock_info(nft_contract.as_str(), &[]), msg).unwrap();
+
+        let filter = DonationFilter {
+            username: alpine_user_b.username.clone(),
+            direction: DonationDirection::Received,
+            from: Some(now.minus_seconds(7 * DAY)),
+            to: Some(now),
+            denom: Some(String::from("earth")),
+            min_amount: Some(Uint128::new(500)),
+            max_amount: None
+        };
+        let msg = QueryMsg::SearchDonations { filter: filter.clone(), start_after: None, limit: Some(1) };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(page.donations[0].0, 4);
+        assert_eq!(page.next_cursor, Some(4));
+
+        let msg = QueryMsg::SearchDonations { filter, start_after: page.next_cursor, limit: Some(1) };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(page.donations[0].0, 5);
+        assert_eq!(page.next_cursor, None);
+
+        // The sender view only has the time filter here, so it includes the NFT gift, and the end of the range is inclusive
+        let filter = DonationFilter {
+            username: alpine_user_a.username,
+            direction: DonationDirection::Sent,
+            from: None,
+            to: Some(now.minus_seconds(2 * DAY)),
+            denom: None,
+            min_amount: None,
+            max_amount: None
+        };
+        let msg = QueryMsg::SearchDonations { filter, start_after: None, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        let ids: Vec<u64> = page.donations.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![1, 6, 2, 3]);
+    }
+
+    // Send a few donations and query the running totals. Should add up every coin and fee, and count each donor once
+    #[test]
+    fn get_donation_aggregates() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let alpine_user_c: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9"),
+            Some(String::from("USER_C")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        for user in [&alpine_user_a, &alpine_user_b, &alpine_user_c] {
+            contract.usernames.save(&mut deps.storage, username_key(&user.username), user).unwrap();
+        }
+
+        let donations = [
+            (&alpine_user_a, vec![coin(1000, "earth")]),
+            (&alpine_user_c, vec![coin(2000, "earth"), coin(100, "mars")]),
+            (&alpine_user_a, vec![coin(500, "earth")])
+        ];
+        for (sender, funds) in donations {
+            let msg = ExecuteMsg::SendDonation { 
+                message: String::from("henlo :)"), 
+                sender: sender.username.clone(),
+                recipient: alpine_user_b.username.clone()
+            };
+            let info = mock_info(sender.address.as_str(), &funds);
+            contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let msg = QueryMsg::GetUserTotals { username: alpine_user_b.username.clone() };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let totals: UserTotalsResponse = from_binary(&res).unwrap();
+        assert_eq!(totals.totals, vec![
+            UserTotals { denom: String::from("earth"), sent: Uint128::zero(), received: Uint128::new(3500) },
+            UserTotals { denom: String::from("mars"), sent: Uint128::zero(), received: Uint128::new(100) }
+        ]);
+
+        let msg = QueryMsg::GetUserTotals { username: alpine_user_a.username };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let totals: UserTotalsResponse = from_binary(&res).unwrap();
+        assert_eq!(totals.totals[0].sent, Uint128::new(1500));
+
+        let msg = QueryMsg::GetUniqueDonors { username: alpine_user_b.username.clone() };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let donors: UniqueDonorsResponse = from_binary(&res).unwrap();
+        assert_eq!(donors.count, 2);
+
+        let msg = QueryMsg::GetLargestDonation { username: alpine_user_b.username, denom: String::from("earth") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let largest: LargestDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(largest.largest, Some(LargestDonation { id: 2, amount: coin(2000, "earth") }));
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetPlatformTotals { }).unwrap();
+        let totals: PlatformTotalsResponse = from_binary(&res).unwrap();
+        assert_eq!(totals.totals, vec![
+            PlatformTotals { denom: String::from("earth"), volume: Uint128::new(3500), fees: Uint128::new(105), donation_count: 3 },
+            PlatformTotals { denom: String::from("mars"), volume: Uint128::new(100), fees: Uint128::new(3), donation_count: 1 }
+        ]);
+    }
+
+    // Build up a recipient's leaderboard. Should rank donors by everything they've given, leaving out donations to yourself
+    #[test]
+    fn get_top_supporters() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let alpine_user_c: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9"),
+            Some(String::from("USER_C")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        for user in [&alpine_user_a, &alpine_user_b, &alpine_user_c] {
+            contract.usernames.save(&mut deps.storage, username_key(&user.username), user).unwrap();
+            contract.addresses.save(&mut deps.storage, user.address.clone(), user).unwrap();
+        }
+
+        let donations = [
+            (&alpine_user_a, coin(1000, "earth")),
+            (&alpine_user_c, coin(2000, "earth")),
+            (&alpine_user_a, coin(1500, "earth")),
+            (&alpine_user_b, coin(5000, "earth")),
+            (&alpine_user_c, coin(9000, "mars"))
+        ];
+        for (sender, amount) in donations {
+            let msg = ExecuteMsg::SendDonation { 
+                message: String::from("henlo :)"), 
+                sender: sender.username.clone(),
+                recipient: alpine_user_b.username.clone()
+            };
+            let info = mock_info(sender.address.as_str(), &[amount]);
+            contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let msg = QueryMsg::GetTopSupporters { recipient: alpine_user_b.username.clone(), denom: String::from("earth"), limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let leaderboard: TopSupportersResponse = from_binary(&res).unwrap();
+        assert_eq!(leaderboard.supporters, vec![
+            TopSupporter { user: alpine_user_a, amount: Uint128::new(2500) },
+            TopSupporter { user: alpine_user_c.clone(), amount: Uint128::new(2000) }
+        ]);
+
+        let msg = QueryMsg::GetTopSupporters { recipient: alpine_user_b.username, denom: String::from("mars"), limit: Some(1) };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let leaderboard: TopSupportersResponse = from_binary(&res).unwrap();
+        assert_eq!(leaderboard.supporters, vec![TopSupporter { user: alpine_user_c, amount: Uint128::new(9000) }]);
+    }
+
+    // Send donations and an NFT gift over a few days and query the daily statistics. Should bucket them by day for the
+    // creator and the platform, and count the gift apart from donations of funds
+    #[test]
+    fn get_daily_stats() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let alpine_user_c: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9"),
+            Some(String::from("USER_C")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        for user in [&alpine_user_a, &alpine_user_b, &alpine_user_c] {
+            contract.usernames.save(&mut deps.storage, username_key(&user.username), user).unwrap();
+        }
+
+        let today = Timestamp::from_seconds(day_of(mock_env().block.time) * SECONDS_PER_DAY);
+        let donations = [
+            (today.minus_seconds(SECONDS_PER_DAY), &alpine_user_b, coin(1000, "earth")),
+            (today, &alpine_user_b, coin(2000, "earth")),
+            (today.plus_seconds(60), &alpine_user_b, coin(100, "mars")),
+            (today.plus_seconds(120), &alpine_user_c, coin(500, "earth"))
+        ];
+        for (time, recipient, amount) in donations {
+            let msg = ExecuteMsg::SendDonation { 
+                message: String::from("henlo :)"), 
+                sender: alpine_user_a.username.clone(),
+                recipient: recipient.username.clone()
+            };
+            let mut env = mock_env();
+            env.block.time = time;
+            let info = mock_info(alpine_user_a.address.as_str(), &[amount]);
+            contract.execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+        let nft_contract = Addr::unchecked("osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9");
+        contract.accepted_nft_contracts.save(&mut deps.storage, &nft_contract, &Empty {}).unwrap();
+        let hook = Cw721HookMsg::SendGift {
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone(),
+            message: String::from("henlo :)")
+        };
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: alpine_user_a.address.to_string(),
+            token_id: String::from("42"),
+            msg: to_binary(&hook).unwrap()
+        });
+        let mut env = mock_env();
+        env.block.time = today.plus_seconds(180);
+        contract.execute(deps.as_mut(), env, mock_info(nft_contract.as_str(), &[]), msg).unwrap();
+
+        let msg = QueryMsg::GetDailyStats {
+            recipient: Some(alpine_user_b.username),
+            from: today.minus_seconds(7 * SECONDS_PER_DAY),
+            to: today,
+            limit: None
+        };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let stats: DailyStatsResponse = from_binary(&res).unwrap();
+        assert_eq!(stats.buckets, vec![
+            DailyStats { day: day_of(today) - 1, count: 1, gift_count: 0, volume: vec![coin(1000, "earth")], fees: vec![coin(30, "earth")] },
+            DailyStats {
+                day: day_of(today),
+                count: 2,
+                gift_count: 1,
+                volume: vec![coin(2000, "earth"), coin(100, "mars")],
+                fees: vec![coin(60, "earth"), coin(3, "mars")]
+            }
+        ]);
+
+        let msg = QueryMsg::GetDailyStats { recipient: None, from: today, to: today, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let stats: DailyStatsResponse = from_binary(&res).unwrap();
+        assert_eq!(stats.buckets.len(), 1);
+        assert_eq!(stats.buckets[0].count, 3);
+        assert_eq!(stats.buckets[0].volume, vec![coin(2500, "earth"), coin(100, "mars")]);
+    }
+
+    // Page through a creator's statement for a period. Should list every coin with its fee and net amount, and total the
+    // whole period on the last page
+    #[test]
+    fn get_statement() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        for user in [&alpine_user_a, &alpine_user_b] {
+            contract.usernames.save(&mut deps.storage, username_key(&user.username), user).unwrap();
+        }
+
+        let start = mock_env().block.time;
+        let donations = [
+            (start, vec![coin(1000, "earth")]),
+            (start.plus_seconds(60), vec![coin(500, "earth"), coin(100, "mars")]),
+            (start.plus_seconds(SECONDS_PER_DAY), vec![coin(2000, "earth")])
+        ];
+        for (time, funds) in donations {
+            let msg = ExecuteMsg::SendDonation { 
+                message: String::from("henlo :)"), 
+                sender: alpine_user_a.username.clone(),
+                recipient: alpine_user_b.username.clone()
+            };
+            let mut env = mock_env();
+            env.block.time = time;
+            let info = mock_info(alpine_user_a.address.as_str(), &funds);
+            contract.execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+
+        let msg = QueryMsg::GetStatement {
+            recipient: alpine_user_b.username.clone(),
+            from: start,
+            to: start.plus_seconds(3600),
+            start_after: None,
+            limit: Some(1)
+        };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let statement: StatementResponse = from_binary(&res).unwrap();
+        assert_eq!(statement.version, STATEMENT_VERSION);
+        assert_eq!(statement.recipient, alpine_user_b.address);
+        assert_eq!(statement.entries, vec![
+            StatementEntry { id: 1, timestamp: start, denom: String::from("earth"), gross: Uint128::new(1000), fee: Uint128::new(30), net: Uint128::new(970) }
+        ]);
+        // Totals only come with the last page, and the cursor carries them until then
+        assert_eq!(statement.totals, None);
+        let cursor = statement.next_cursor.unwrap();
+        assert_eq!(cursor.id, 1);
+        assert_eq!(cursor.totals, vec![
+            StatementTotal { denom: String::from("earth"), gross: Uint128::new(1000), fee: Uint128::new(30), net: Uint128::new(970), count: 1 }
+        ]);
+
+        let msg = QueryMsg::GetStatement {
+            recipient: alpine_user_b.username,
+            from: start,
+            to: start.plus_seconds(3600),
+            start_after: Some(cursor),
+            limit: Some(1)
+        };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let statement: StatementResponse = from_binary(&res).unwrap();
+        let denoms: Vec<(u64, String)> = statement.entries.into_iter().map(|e| (e.id, e.denom)).collect();
+        assert_eq!(denoms, vec![(2, String::from("earth")), (2, String::from("mars"))]);
+        assert_eq!(statement.totals, Some(vec![
+            StatementTotal { denom: String::from("earth"), gross: Uint128::new(1500), fee: Uint128::new(45), net: Uint128::new(1455), count: 2 },
+            StatementTotal { denom: String::from("mars"), gross: Uint128::new(100), fee: Uint128::new(3), net: Uint128::new(97), count: 1 }
+        ]));
+        assert_eq!(statement.next_cursor, None);
+    }
+
+    // Simulate donations from a user before sending them. Should return the split that sending would use, or the error it
+    // would fail with
+    #[test]
+    fn simulate_donation() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        for user in [&alpine_user_a, &alpine_user_b] {
+            contract.usernames.save(&mut deps.storage, username_key(&user.username), user).unwrap();
+            contract.addresses.save(&mut deps.storage, user.address.clone(), user).unwrap();
+        }
+
+        let msg = QueryMsg::SimulateDonation {
+            sender: alpine_user_a.address.to_string(),
+            recipient: alpine_user_b.username.clone(),
+            message: None,
+            funds: vec![coin(1000, "earth"), coin(100, "mars")]
+        };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let simulation: SimulateDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(simulation.error, None);
+        assert_eq!(simulation.fee_bps, Some(300));
+        assert_eq!(simulation.fee_collector, Some(Addr::unchecked("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a")));
+        assert_eq!(simulation.splits, vec![
+            CoinSplit { gross: coin(1000, "earth"), fee: coin(30, "earth"), net: coin(970, "earth") },
+            CoinSplit { gross: coin(100, "mars"), fee: coin(3, "mars"), net: coin(97, "mars") }
+        ]);
+
+        // Sending the same donation should move the simulated amounts
+        let msg = ExecuteMsg::SendDonation { 
+            message: String::from(""), 
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone()
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &[coin(1000, "earth")]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes.iter().find(|a| a.key == "net_amount").unwrap().value, "970earth");
+
+        let failures = [
+            (vec![coin(1000, "venus")], alpine_user_b.username.clone(), ContractError::DenomNotAccepted { denom: String::from("venus") }),
+            (vec![], alpine_user_b.username.clone(), ContractError::NoDonation {}),
+            (vec![coin(1000, "earth")], String::from("nobody"), ContractError::UserNotFound { user: String::from("nobody") })
+        ];
+        for (funds, recipient, err) in failures {
+            let msg = QueryMsg::SimulateDonation {
+                sender: alpine_user_a.address.to_string(),
+                recipient,
+                message: None,
+                funds
+            };
+            let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+            let simulation: SimulateDonationResponse = from_binary(&res).unwrap();
+            assert_eq!(simulation.error, Some(err.to_string()));
+            assert!(simulation.splits.is_empty());
+            assert_eq!(simulation.fee_bps, None);
+        }
+    }
+
+    // Page through received donations. Should return them in the order they were sent, including past the 9th donation
+    #[test]
+    fn get_received_donations_paginated() {
+        let mut deps = mock_dependencies();
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh"),
+            Some(String::from("USER_A"))
+        ).unwrap();
+        let alpine_user_b: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B")) 
+        ).unwrap();
+        let contract = setup_contract(deps.as_mut());
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        for i in 0..12 {
+            let msg = ExecuteMsg::SendDonation { 
+                message: String::from("henlo :)"), 
+                sender: alpine_user_a.username.clone(),
+                recipient: alpine_user_b.username.clone()
+            };
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(i);
+            let info = mock_info(alpine_user_a.address.as_str(), &coins(1000, "earth"));
+            contract.execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+
+        let msg = QueryMsg::GetReceivedDonations { recipient: alpine_user_b.username.clone(), start_after: None, limit: Some(5) };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        let ids: Vec<u64> = page.donations.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(page.next_cursor, Some(5));
+
+        let msg = QueryMsg::GetReceivedDonations { recipient: alpine_user_b.username.clone(), start_after: Some(9), limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        let ids: Vec<u64> = page.donations.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![10, 11, 12]);
+        assert_eq!(page.next_cursor, None);
+
+        // Limits above the maximum are capped
+        let msg = QueryMsg::GetSentDonations { sender: alpine_user_a.username, start_after: None, limit: Some(100) };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(page.donations.len(), 12);
+    }
+}
+
+// Define a set of integration tests that use our entry points instead of internal calls
+#[cfg(test)]
+mod integration_tests {
+    use cosmwasm_std::{testing::{mock_dependencies, mock_info, mock_env}, Addr, from_binary};
+
+    use crate::{InstantiateMsg, entry::{instantiate, migrate, query, execute}, MigrateMsg, state::AlpineUser, ExecuteMsg, QueryMsg, msg::MultiUserResponse};
+
+    // Validate that instantiation works from the client's perspective
+    #[test]
+    fn proper_instantiation() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 300,
+            fee_collector: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![]
+        };
+        let info = mock_info("creator", &[]);
+
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+    }
+
+    // Validate that migration works from the client's perspective
+    #[test]
+    fn proper_migration() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 300,
+            fee_collector: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![]
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = MigrateMsg { owner: None };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(0, res.messages.len())
+    }
+
+    // Validate that execution works from the client's perspective
+    #[test]
+    fn successful_execute() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 300,
+            fee_collector: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![]
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1tpsscvhaddf36gjvnyjhtwsyempptupypngxzs"),
+            Some(String::from(""))
+        ).unwrap();
+        let msg = ExecuteMsg::RegisterUser {
+            user: alpine_user_a.clone(),
+            username: String::from("a_tester")
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &[]);
+        
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    }
+
+    // Validate that queries work from the client's perspective
+    #[test]
+    fn successful_query() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 300,
+            fee_collector: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![]
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let alpine_user_a: AlpineUser = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1tpsscvhaddf36gjvnyjhtwsyempptupypngxzs"),
+            Some(String::from(""))
+        ).unwrap();
+        let msg = ExecuteMsg::RegisterUser {
+            user: alpine_user_a.clone(),
+            username: String::from("a_tester")
+        };
+        let info = mock_info(alpine_user_a.address.as_str(), &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::GetAllUsers { start_after: None, limit: None };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let users: MultiUserResponse  = from_binary(&res).unwrap();
+        assert_eq!(users.users.len(), 1)
+    }
+}
+// A set of tests for the fee configuration
+#[cfg(test)]
+mod config_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, coin, coins, Addr, BankMsg, CosmosMsg, DepsMut, Uint128};
+
+    use crate::msg::{
+        InstantiateMsg,
+        ExecuteMsg,
+        QueryMsg,
+        ConfigResponse,
+        PendingFeeChangeResponse
+    };
+    use crate::{
+        ContractError,
+        state::{
+            AlpineContract,
+            AlpineUser,
+            username_key,
+            AcceptedDenom
+        }
+    };
+
+    const ADMIN: &str = "osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh";
+    const COLLECTOR: &str = "osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9";
+
+    const NOTICE_PERIOD: u64 = 24 * 60 * 60;
+
+    // A utility function to set up a contract with a 5% fee and a day of notice for fee changes
+    fn setup_contract(deps: DepsMut<'_>) -> AlpineContract<'static> {
+        let contract = AlpineContract::default();
+        let msg = InstantiateMsg {
+            owner: Some(String::from(ADMIN)),
+            fee_bps: 500,
+            fee_collector: String::from(COLLECTOR),
+            max_fee_bps: 1_000,
+            fee_notice_period: NOTICE_PERIOD,
+            accepted_denoms: vec![
+                AcceptedDenom {
+                    denom: String::from("earth"),
+                    symbol: String::from("EARTH"),
+                    decimals: 6,
+                    min_amount: Uint128::new(1)
+                }
+            ]
+        };
+        let info = mock_info("creator", &[]);
+
+        let res = contract.instantiate(deps, mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        contract
+    }
+
+    // Attempt to instantiate with a fee above the maximum fee. Should error out
+    #[test]
+    fn instantiate_fee_above_max() {
+        let mut deps = mock_dependencies();
+        let contract = AlpineContract::default();
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 1_500,
+            fee_collector: String::from(COLLECTOR),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![
+                AcceptedDenom {
+                    denom: String::from("earth"),
+                    symbol: String::from("EARTH"),
+                    decimals: 6,
+                    min_amount: Uint128::new(1)
+                }
+            ]
+        };
+        let info = mock_info("creator", &[]);
+
+        let res = contract.instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidFee { fee_bps: 1_500, max_fee_bps: 1_000 });
+    }
+
+    // Attempt to instantiate with less than a day of notice for fee changes. Should error out
+    #[test]
+    fn instantiate_notice_period_below_min() {
+        let mut deps = mock_dependencies();
+        let contract = AlpineContract::default();
+        let msg = InstantiateMsg {
+            owner: None,
+            fee_bps: 300,
+            fee_collector: String::from(COLLECTOR),
+            max_fee_bps: 1_000,
+            fee_notice_period: NOTICE_PERIOD - 1,
+            accepted_denoms: vec![]
+        };
+        let info = mock_info("creator", &[]);
+
+        let res = contract.instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidFeeNoticePeriod { fee_notice_period: NOTICE_PERIOD - 1, min_fee_notice_period: NOTICE_PERIOD });
+    }
+
+    // Query the config set during instantiation. Should return success
+    #[test]
+    fn get_config() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetConfig { }).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.config.fee_bps, 500);
+        assert_eq!(config.config.fee_collector, Addr::unchecked(COLLECTOR));
+        assert_eq!(config.config.max_fee_bps, 1_000);
+    }
+
+    // Attempt to update the config from an address that isn't a fee manager. Should error out
+    #[test]
+    fn update_config_unauthorized() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig { fee_bps: Some(0), fee_collector: None };
+        let info = mock_info(COLLECTOR, &[]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+    }
+
+    // Attempt to raise the fee above the maximum fee. Should error out
+    #[test]
+    fn update_config_fee_above_max() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig { fee_bps: Some(1_001), fee_collector: None };
+        let info = mock_info(ADMIN, &[]);
+        let res = contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidFee { fee_bps: 1_001, max_fee_bps: 1_000 });
+    }
+
+    // Propose a fee change and validate that it's pending until the notice period passes. Should return success
+    #[test]
+    fn fee_change_pending_until_effective() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig { fee_bps: Some(250), fee_collector: None };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetConfig { }).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.config.fee_bps, 500);
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetPendingFeeChange { }).unwrap();
+        let pending: PendingFeeChangeResponse = from_binary(&res).unwrap();
+        let pending = pending.pending.unwrap();
+        assert_eq!(pending.fee_bps, 250);
+        assert_eq!(pending.effective_at, mock_env().block.time.plus_seconds(NOTICE_PERIOD));
+
+        let mut env = mock_env();
+        env.block.time = pending.effective_at;
+        let res = contract.query(deps.as_ref(), env.clone(), QueryMsg::GetConfig { }).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.config.fee_bps, 250);
+
+        let res = contract.query(deps.as_ref(), env, QueryMsg::GetPendingFeeChange { }).unwrap();
+        let pending: PendingFeeChangeResponse = from_binary(&res).unwrap();
+        assert_eq!(pending.pending, None);
+    }
+
+    // Cancel a pending fee change. The current fee should stay in force
+    #[test]
+    fn cancel_fee_change() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig { fee_bps: Some(250), fee_collector: None };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        contract.execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), ExecuteMsg::CancelFeeChange { }).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(NOTICE_PERIOD);
+        let res = contract.query(deps.as_ref(), env.clone(), QueryMsg::GetConfig { }).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.config.fee_bps, 500);
+
+        let res = contract.execute(deps.as_mut(), env, mock_info(ADMIN, &[]), ExecuteMsg::CancelFeeChange { }).unwrap_err();
+        assert_eq!(res, ContractError::NoPendingFeeChange {});
+    }
+
+    // Update the fee and validate that donations use it once it's in force. Should return success
+    #[test]
+    fn send_donation_uses_configured_fee() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let alpine_user_a = AlpineUser::new(deps.as_ref(), Addr::unchecked(ADMIN), Some(String::from("USER_A"))).unwrap();
+        let alpine_user_b = AlpineUser::new(
+            deps.as_ref(),
+            Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"),
+            Some(String::from("USER_B"))
+        ).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig { fee_bps: Some(250), fee_collector: None };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SendDonation {
+            message: String::from("henlo :)"),
+            sender: alpine_user_a.username,
+            recipient: alpine_user_b.username
+        };
+        let info = mock_info(ADMIN, &coins(1000, "earth"));
+        let res = contract.execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(COLLECTOR),
+            amount: coins(50, "earth")
+        }));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(NOTICE_PERIOD);
+        let res = contract.execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: alpine_user_b.address.to_string(),
+            amount: coins(975, "earth")
+        }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(COLLECTOR),
+            amount: coins(25, "earth")
+        }));
+    }
+
+    // Attempt to recover funds from an address that isn't the owner. Should error out
+    #[test]
+    fn recover_funds_unauthorized() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::RecoverFunds { recipient: String::from(COLLECTOR), amount: None };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(COLLECTOR, &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+    }
+
+    // Recover the whole balance stranded in the contract. Should return success
+    #[test]
+    fn recover_funds_entire_balance() {
+        let mut deps = mock_dependencies_with_balance(&[coin(50, "earth"), coin(7, "mars")]);
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::RecoverFunds { recipient: String::from(COLLECTOR), amount: None };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(COLLECTOR),
+            amount: vec![coin(50, "earth"), coin(7, "mars")]
+        }));
+    }
+
+    // Attempt to recover funds when the contract doesn't hold any. Should error out
+    #[test]
+    fn recover_funds_empty_balance() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::RecoverFunds { recipient: String::from(COLLECTOR), amount: None };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::NoFundsToRecover {});
+    }
+}
+// A set of tests for migrating older deployments
+#[cfg(test)]
+mod migration_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{from_binary, coin, coins, Addr, Attribute, Order, StdResult, Uint128};
+    use cw2::set_contract_version;
+
+    use crate::msg::{
+        MigrateMsg,
+        QueryMsg,
+        MultiDonationResponse
+    };
+    use crate::{
+        state::{
+            AlpineContract,
+            AlpineUser,
+            username_key,
+            CoinSplit,
+            PlatformTotals,
+            DailyStats,
+            USERNAME_REGISTRATION_PERIOD
+        },
+        migrations::{legacy_donations, LegacyDonationInfo}
+    };
+
+    const ADMIN: &str = "osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh";
+    const COLLECTOR: &str = "osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9";
+
+    // Migrate a deployment which predates the config. Should fill in the legacy fee settings
+    #[test]
+    fn migrate_fills_legacy_config() {
+        let mut deps = mock_dependencies();
+        let contract = AlpineContract::default();
+        set_contract_version(&mut deps.storage, "crates.io:alpine-pay", "0.1.0").unwrap();
+
+        contract.migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: Some(String::from(ADMIN)) }).unwrap();
+        let config = contract.config.load(&deps.storage).unwrap();
+        assert_eq!(contract.owner.load(&deps.storage).unwrap(), Addr::unchecked(ADMIN));
+        assert_eq!(config.fee_bps, 300);
+        assert_eq!(config.fee_collector, Addr::unchecked("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"));
+        assert!(contract.accepted_denoms.has(&deps.storage, "uosmo"));
+    }
+
+    // Migrate a deployment whose usernames were keyed by their registered casing. Should rebuild the index
+    // under lowercased keys, with the first colliding username keeping the name
+    #[test]
+    fn migrate_rebuilds_username_index() {
+        let mut deps = mock_dependencies();
+        let contract = AlpineContract::default();
+        set_contract_version(&mut deps.storage, "crates.io:alpine-pay", "0.1.0").unwrap();
+
+        let user_a = AlpineUser::new(deps.as_ref(), Addr::unchecked(ADMIN), Some(String::from("Alice"))).unwrap();
+        let user_b = AlpineUser::new(deps.as_ref(), Addr::unchecked(COLLECTOR), Some(String::from("alice"))).unwrap();
+        let user_c = AlpineUser::new(deps.as_ref(), Addr::unchecked("osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x"), Some(String::from("Bob"))).unwrap();
+        for user in [&user_a, &user_b, &user_c] {
+            contract.usernames.save(&mut deps.storage, user.username.clone(), user).unwrap();
+            contract.addresses.save(&mut deps.storage, user.address.clone(), user).unwrap();
+        }
+
+        let res = contract.migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+        assert_eq!(res.attributes[0].value, "alice");
+        // The colliding user is unregistered, so it can't act on the username it lost
+        assert!(!contract.addresses.has(&deps.storage, user_b.address.clone()));
+        assert_eq!(contract.addresses.load(&deps.storage, user_a.address.clone()).unwrap(), user_a);
+        assert_eq!(contract.find_alpine_username(&deps.storage, String::from("ALICE"), mock_env().block.time).unwrap(), user_a);
+        assert_eq!(contract.find_alpine_username(&deps.storage, String::from("bob"), mock_env().block.time).unwrap(), user_c);
+        assert!(!contract.usernames.has(&deps.storage, String::from("Bob")));
+
+        // Usernames from before expiry existed get a full registration period
+        assert!(res.attributes.contains(&Attribute::new("expiry_backfilled_usernames", "2")));
+        assert_eq!(
+            contract.username_expiries.load(&deps.storage, String::from("bob")).unwrap(),
+            mock_env().block.time.plus_seconds(USERNAME_REGISTRATION_PERIOD)
+        );
+    }
+
+    // Migrate a deployment which already keys usernames by their lowercased name and expires them. Should leave the
+    // username index and expiries alone
+    #[test]
+    fn migrate_skips_current_username_index() {
+        let mut deps = mock_dependencies();
+        let contract = AlpineContract::default();
+        set_contract_version(&mut deps.storage, "crates.io:alpine-pay", env!("CARGO_PKG_VERSION")).unwrap();
+
+        let user = AlpineUser::new(deps.as_ref(), Addr::unchecked(ADMIN), Some(String::from("Bob"))).unwrap();
+        contract.usernames.save(&mut deps.storage, user.username.clone(), &user).unwrap();
+
+        let res = contract.migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+        assert!(contract.usernames.has(&deps.storage, String::from("Bob")));
+        assert!(!contract.usernames.has(&deps.storage, String::from("bob")));
+        assert!(res.attributes.contains(&Attribute::new("expiry_backfilled_usernames", "0")));
+        assert!(!contract.username_expiries.has(&deps.storage, String::from("Bob")));
+    }
+
+    // Migrate a deployment whose donations were keyed by their ID as a string and embedded full users. Should move them
+    // to numeric keys with address references and index them, so that donations page in the order they were sent,
+    // record the legacy 3% fee on them, and add them to the totals, leaderboards and daily statistics
+    #[test]
+    fn migrate_legacy_donations() {
+        let mut deps = mock_dependencies();
+        let contract = AlpineContract::default();
+        set_contract_version(&mut deps.storage, "crates.io:alpine-pay", "0.1.0").unwrap();
+
+        let sender = AlpineUser::new(deps.as_ref(), Addr::unchecked(ADMIN), Some(String::from("USER_A"))).unwrap();
+        let recipient = AlpineUser::new(deps.as_ref(), Addr::unchecked(COLLECTOR), Some(String::from("USER_B"))).unwrap();
+        for user in [&sender, &recipient] {
+            contract.usernames.save(&mut deps.storage, username_key(&user.username), user).unwrap();
+            contract.addresses.save(&mut deps.storage, user.address.clone(), user).unwrap();
+        }
+
+        // String IDs sort 10, 11 and 12 before 2
+        let legacy = legacy_donations();
+        for id in 1..=12u64 {
+            let donation = LegacyDonationInfo {
+                sender: sender.clone(),
+                recipient: recipient.clone(),
+                amount: coins(1000, "uosmo"),
+                message: id.to_string(),
+                timestamp: Some(mock_env().block.time.plus_seconds(id))
+            };
+            legacy.save(&mut deps.storage, &id.to_string(), &donation).unwrap();
+        }
+
+        let res = contract.migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+        assert!(res.attributes.contains(&Attribute::new("migrated_donations", "12")));
+        assert_eq!(legacy.range(&deps.storage, None, None, Order::Ascending).count(), 0);
+
+        assert_eq!(contract.platform_totals.load(&deps.storage, "uosmo").unwrap(), PlatformTotals {
+            denom: String::from("uosmo"),
+            volume: Uint128::new(12_000),
+            fees: Uint128::new(360),
+            donation_count: 12
+        });
+        assert_eq!(contract.user_totals.load(&deps.storage, (&recipient.address, "uosmo")).unwrap().received, Uint128::new(12_000));
+        assert_eq!(contract.unique_donors.load(&deps.storage, &recipient.address).unwrap(), 1);
+        assert_eq!(contract.supporter_totals.load(&deps.storage, (&recipient.address, "uosmo", &sender.address)).unwrap(), Uint128::new(12_000));
+        let days: Vec<(u64, DailyStats)> = contract.platform_daily_stats
+            .range(&deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].1.count, 12);
+        assert_eq!(days[0].1.fees, coins(360, "uosmo"));
+
+        let msg = QueryMsg::GetReceivedDonations { recipient: recipient.username.clone(), start_after: Some(6), limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: MultiDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(page.donations[0].1.sender, sender);
+        assert_eq!(page.donations[0].1.sender_username_at_donation, "USER_A");
+        assert_eq!(page.donations[0].1.fee_bps, 300);
+        assert_eq!(page.donations[0].1.splits, vec![
+            CoinSplit { gross: coin(1000, "uosmo"), fee: coin(30, "uosmo"), net: coin(970, "uosmo") }
+        ]);
+        let messages: Vec<String> = page.donations.into_iter().map(|(_, d)| d.message).collect();
+        assert_eq!(messages, vec!["7", "8", "9", "10", "11", "12"]);
+
+        // Migrating again leaves the donations where they are
+        let res = contract.migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+        assert!(res.attributes.contains(&Attribute::new("migrated_donations", "0")));
+        assert_eq!(contract.platform_totals.load(&deps.storage, "uosmo").unwrap().donation_count, 12);
+    }
+}
+
+// A set of tests for ownership and roles
+#[cfg(test)]
+mod access_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, coins, Addr, DepsMut, Uint128};
+
+    use crate::msg::{
+        InstantiateMsg,
+        ExecuteMsg,
+        QueryMsg,
+        OwnershipResponse,
+        RoleHoldersResponse,
+        PauseStateResponse,
+        MultiUserResponse,
+        AlpineUserResponse,
+        UsernameAvailableResponse,
+        ReservedUsernamesResponse,
+        BlockedPatternsResponse
+    };
+    use crate::{
+        ContractError,
+        state::{
+            AlpineContract,
+            AlpineUser,
+            username_key,
+            AcceptedDenom,
+            Role,
+            PatternKind,
+            UnavailableReason
+        }
+    };
+
+    const OWNER: &str = "osmo1409ep5zmpxyrh5jpxc8tcw4c0wppkvlqpya9jh";
+    const NEW_OWNER: &str = "osmo1ayjl4cm8e2nrnhstx92cr6uuljnumjxgkncs7x";
+    const FEE_MANAGER: &str = "osmo1hrm44y69kzdjqq2tn6hh9cq3tzmfsa9rfgv7d9";
+    const MODERATOR: &str = "osmo1ysehn88p24d7769j4vj07hyndkjj7pccz3j3c9";
+    const VERIFIER: &str = "osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a";
+
+    // A utility function to set up a contract
+    fn setup_contract(deps: DepsMut<'_>) -> AlpineContract<'static> {
+        let contract = AlpineContract::default();
+        let msg = InstantiateMsg {
+            owner: Some(String::from(OWNER)),
+            fee_bps: 300,
+            fee_collector: String::from("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a"),
+            max_fee_bps: 1_000,
+            fee_notice_period: 24 * 60 * 60,
+            accepted_denoms: vec![
+                AcceptedDenom {
+                    denom: String::from("earth"),
+                    symbol: String::from("EARTH"),
+                    decimals: 6,
+                    min_amount: Uint128::new(1)
+                }
+            ]
+        };
+        let info = mock_info("creator", &[]);
+
+        let res = contract.instantiate(deps, mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        contract
+    }
+
+    // Transfer ownership through a proposal and acceptance. Should return success
+    #[test]
+    fn transfer_ownership() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::ProposeOwner { new_owner: String::from(NEW_OWNER) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetOwnership { }).unwrap();
+        let ownership: OwnershipResponse = from_binary(&res).unwrap();
+        assert_eq!(ownership.owner, Addr::unchecked(OWNER));
+        assert_eq!(ownership.pending_owner, Some(Addr::unchecked(NEW_OWNER)));
+
+        contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), ExecuteMsg::AcceptOwnership { }).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetOwnership { }).unwrap();
+        let ownership: OwnershipResponse = from_binary(&res).unwrap();
+        assert_eq!(ownership.owner, Addr::unchecked(NEW_OWNER));
+        assert_eq!(ownership.pending_owner, None);
+    }
+
+    // Attempt to accept ownership from an address that wasn't nominated. Should error out
+    #[test]
+    fn accept_ownership_wrong_address() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), ExecuteMsg::AcceptOwnership { }).unwrap_err();
+        assert_eq!(res, ContractError::NoPendingOwner {});
+
+        let msg = ExecuteMsg::ProposeOwner { new_owner: String::from(NEW_OWNER) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), ExecuteMsg::AcceptOwnership { }).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+    }
+
+    // Grant the fee manager role, then validate that the role holder can propose fee changes. Should return success
+    #[test]
+    fn grant_fee_manager() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig { fee_bps: Some(100), fee_collector: None };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+
+        let grant = ExecuteMsg::GrantRole { role: Role::FeeManager, address: String::from(FEE_MANAGER) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), grant).unwrap();
+        contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetRoleHolders { role: Role::FeeManager }).unwrap();
+        let holders: RoleHoldersResponse = from_binary(&res).unwrap();
+        assert_eq!(holders.holders, vec![Addr::unchecked(FEE_MANAGER)]);
+    }
+
+    // Revoke a role, then validate that the former holder loses access. Should return success
+    #[test]
+    fn revoke_role() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let grant = ExecuteMsg::GrantRole { role: Role::FeeManager, address: String::from(FEE_MANAGER) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), grant).unwrap();
+        let revoke = ExecuteMsg::RevokeRole { role: Role::FeeManager, address: String::from(FEE_MANAGER) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), revoke.clone()).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig { fee_bps: Some(100), fee_collector: None };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), revoke).unwrap_err();
+        assert_eq!(res, ContractError::RoleNotHeld { role: String::from("fee_manager"), address: String::from(FEE_MANAGER) });
+    }
+
+    // Attempt to grant roles without being the owner, or to grant ownership as a role. Should error out
+    #[test]
+    fn grant_role_invalid() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let grant = ExecuteMsg::GrantRole { role: Role::Moderator, address: String::from(FEE_MANAGER) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), grant).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+
+        let grant = ExecuteMsg::GrantRole { role: Role::Owner, address: String::from(FEE_MANAGER) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), grant).unwrap_err();
+        assert_eq!(res, ContractError::InvalidRole { role: String::from("owner") });
+    }
+
+    // Pause donations as a pauser. Donations should be rejected while queries keep working
+    #[test]
+    fn pause_donations() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let alpine_user_a = AlpineUser::new(deps.as_ref(), Addr::unchecked(OWNER), Some(String::from("USER_A"))).unwrap();
+        let alpine_user_b = AlpineUser::new(deps.as_ref(), Addr::unchecked(NEW_OWNER), Some(String::from("USER_B"))).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_a.username), &alpine_user_a).unwrap();
+        contract.usernames.save(&mut deps.storage, username_key(&alpine_user_b.username), &alpine_user_b).unwrap();
+
+        let grant = ExecuteMsg::GrantRole { role: Role::Pauser, address: String::from(FEE_MANAGER) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), grant).unwrap();
+        let pause = ExecuteMsg::SetPaused { donations: Some(true), registrations: None, profile_updates: None };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), pause).unwrap();
+
+        let msg = ExecuteMsg::SendDonation {
+            message: String::from("henlo :)"),
+            sender: alpine_user_a.username.clone(),
+            recipient: alpine_user_b.username.clone()
+        };
+        let info = mock_info(OWNER, &coins(1000, "earth"));
+        let res = contract.execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::Paused { operation: String::from("donations") });
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetPauseState { }).unwrap();
+        let pause_state: PauseStateResponse = from_binary(&res).unwrap();
+        assert!(pause_state.pause_state.donations);
+        assert!(!pause_state.pause_state.registrations);
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetAllUsers { start_after: None, limit: None }).unwrap();
+        let users: MultiUserResponse = from_binary(&res).unwrap();
+        assert_eq!(users.users.len(), 2);
+
+        let unpause = ExecuteMsg::SetPaused { donations: Some(false), registrations: None, profile_updates: None };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), unpause).unwrap();
+        contract.execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    // Pause registrations as the owner. Registrations should be rejected
+    #[test]
+    fn pause_registrations() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let pause = ExecuteMsg::SetPaused { donations: None, registrations: Some(true), profile_updates: None };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), pause).unwrap();
+
+        let user = AlpineUser::new(deps.as_ref(), Addr::unchecked(NEW_OWNER), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user, username: String::from("alpine_user_1") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::Paused { operation: String::from("registrations") });
+    }
+
+    // Pause profile updates as the owner. Username changes should be rejected
+    #[test]
+    fn pause_profile_updates() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let user = AlpineUser::new(deps.as_ref(), Addr::unchecked(NEW_OWNER), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user, username: String::from("alpine_user_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), msg).unwrap();
+
+        let pause = ExecuteMsg::SetPaused { donations: None, registrations: None, profile_updates: Some(true) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), pause).unwrap();
+
+        let msg = ExecuteMsg::ChangeUsername { username: String::from("alpine_user_2") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::Paused { operation: String::from("profile_updates") });
+    }
+
+    // Reserve a username as a moderator, then allocate it to a creator as a verifier. Should keep anybody else from
+    // registering it, and only let a verifier allocate it
+    #[test]
+    fn allocate_reserved_name() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        for (role, address) in [(Role::Moderator, MODERATOR), (Role::Verifier, VERIFIER)] {
+            let msg = ExecuteMsg::GrantRole { role, address: String::from(address) };
+            contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::ReserveUsernames { usernames: vec![String::from("Alpine"), String::from("support")] };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(VERIFIER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MODERATOR, &[]), msg).unwrap();
+
+        let msg = QueryMsg::IsUsernameAvailable { username: String::from("ALPINE") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let availability: UsernameAvailableResponse = from_binary(&res).unwrap();
+        assert!(!availability.is_available);
+        assert_eq!(availability.reason, Some(UnavailableReason::Reserved));
+
+        let user = AlpineUser::new(deps.as_ref(), Addr::unchecked(NEW_OWNER), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user, username: String::from("alpine") };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameReserved { username: String::from("alpine") });
+
+        let msg = ExecuteMsg::AllocateReservedName { username: String::from("alpine"), address: String::from(NEW_OWNER) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(MODERATOR, &[]), msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+        contract.execute(deps.as_mut(), mock_env(), mock_info(VERIFIER, &[]), msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetUserByAddr { address: Addr::unchecked(NEW_OWNER) }).unwrap();
+        let user: AlpineUserResponse = from_binary(&res).unwrap();
+        assert_eq!(user.user.username, "alpine");
+
+        let msg = QueryMsg::GetReservedUsernames { start_after: None, limit: None };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let reserved: ReservedUsernamesResponse = from_binary(&res).unwrap();
+        assert_eq!(reserved.usernames, vec![String::from("support")]);
+
+        // Only reserved usernames can be allocated
+        let msg = ExecuteMsg::AllocateReservedName { username: String::from("alpine_user_1"), address: String::from(FEE_MANAGER) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(VERIFIER, &[]), msg).unwrap_err();
+        assert_eq!(res, ContractError::UsernameNotReserved { username: String::from("alpine_user_1") });
+    }
+
+    // Block a few patterns as a moderator and register usernames against them. Should reject the usernames which match
+    // a pattern regardless of casing
+    #[test]
+    fn block_username_patterns() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+        let msg = ExecuteMsg::GrantRole { role: Role::Moderator, address: String::from(MODERATOR) };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        let patterns = [(PatternKind::Prefix, "admin"), (PatternKind::Suffix, "_official"), (PatternKind::Substring, "alpine")];
+        for (kind, pattern) in patterns {
+            let msg = ExecuteMsg::BlockPattern { kind, pattern: String::from(pattern) };
+            contract.execute(deps.as_mut(), mock_env(), mock_info(MODERATOR, &[]), msg).unwrap();
+        }
+
+        for username in ["Admin_1", "creator_OFFICIAL", "my-alpine-page"] {
+            let user = AlpineUser::new(deps.as_ref(), Addr::unchecked(NEW_OWNER), None).unwrap();
+            let msg = ExecuteMsg::RegisterUser { user, username: String::from(username) };
+            let res = contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), msg).unwrap_err();
+            assert_eq!(res, ContractError::UsernameBlocked { username: String::from(username) });
+        }
+
+        let msg = QueryMsg::IsUsernameAvailable { username: String::from("superadmin") };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        let availability: UsernameAvailableResponse = from_binary(&res).unwrap();
+        assert!(availability.is_available);
+
+        // Unblocking a pattern lets usernames which match it be registered again
+        let msg = ExecuteMsg::UnblockPattern { kind: PatternKind::Prefix, pattern: String::from("ADMIN") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MODERATOR, &[]), msg).unwrap();
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetBlockedPatterns { }).unwrap();
+        let blocked: BlockedPatternsResponse = from_binary(&res).unwrap();
+        assert_eq!(blocked.patterns.len(), 2);
+
+        let user = AlpineUser::new(deps.as_ref(), Addr::unchecked(NEW_OWNER), None).unwrap();
+        let msg = ExecuteMsg::RegisterUser { user, username: String::from("Admin_1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(NEW_OWNER, &[]), msg).unwrap();
+    }
+
+    // Attempt to pause the contract without the pauser role. Should error out
+    #[test]
+    fn pause_unauthorized() {
+        let mut deps = mock_dependencies();
+        let contract = setup_contract(deps.as_mut());
+
+        let pause = ExecuteMsg::SetPaused { donations: Some(true), registrations: Some(true), profile_updates: Some(true) };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(FEE_MANAGER, &[]), pause).unwrap_err();
+        assert_eq!(res, ContractError::Unauthorized {});
+    }
+}
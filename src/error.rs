@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Timestamp, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -25,4 +25,54 @@ pub enum ContractError {
     NoDonation {},
     #[error("Your donation message can't be more than 250 characters")]
     DonationMessageTooLong {},
+    #[error("Invalid fee ({fee_bps:?} bps) - cannot exceed {max_fee_bps:?} bps")]
+    InvalidFee { fee_bps: u64, max_fee_bps: u64 },
+    #[error("Invalid fee notice period ({fee_notice_period:?} seconds) - must be at least {min_fee_notice_period:?} seconds")]
+    InvalidFeeNoticePeriod { fee_notice_period: u64, min_fee_notice_period: u64 },
+    #[error("There are no funds to recover")]
+    NoFundsToRecover {},
+    #[error("There is no pending fee change")]
+    NoPendingFeeChange {},
+    #[error("There is no pending owner")]
+    NoPendingOwner {},
+    #[error("Role ({role:?}) can't be granted")]
+    InvalidRole { role: String },
+    #[error("Address ({address:?}) doesn't hold role ({role:?})")]
+    RoleNotHeld { role: String, address: String },
+    #[error("The contract is paused for {operation:?}")]
+    Paused { operation: String },
+    #[error("Denom not accepted ({denom:?})")]
+    DenomNotAccepted { denom: String },
+    #[error("NFT contract not accepted ({contract_addr:?})")]
+    NftContractNotAccepted { contract_addr: String },
+    #[error("Donations in {denom:?} must be at least {min_amount}")]
+    DonationBelowMinimum { denom: String, min_amount: Uint128 },
+    #[error("Invalid denom ({denom:?}) - the denom and symbol can't be empty")]
+    InvalidDenom { denom: String },
+    #[error("The username can't be changed again until {available_at}")]
+    UsernameChangeCooldown { available_at: Timestamp },
+    #[error("The price must be more than zero")]
+    InvalidPrice {},
+    #[error("Username has expired ({username:?})")]
+    UsernameExpired { username: String },
+    #[error("Username isn't listed for sale ({username:?})")]
+    UsernameNotListed { username: String },
+    #[error("No renewal fee is set for usernames as long as ({username:?})")]
+    RenewalFeeNotSet { username: String },
+    #[error("Only native denoms can pay for usernames ({denom:?})")]
+    NativeDenomRequired { denom: String },
+    #[error("Incorrect payment - send exactly {expected}")]
+    IncorrectPayment { expected: String },
+    #[error("There is no pending transfer of the username ({username:?})")]
+    NoPendingUsernameTransfer { username: String },
+    #[error("Username is reserved ({username:?})")]
+    UsernameReserved { username: String },
+    #[error("Username isn't reserved ({username:?})")]
+    UsernameNotReserved { username: String },
+    #[error("Username matches a blocked pattern ({username:?})")]
+    UsernameBlocked { username: String },
+    #[error("Invalid pattern ({pattern:?}) - the pattern can't be empty and has to be blocked to unblock it")]
+    InvalidPattern { pattern: String },
+    #[error("Username ({username:?}) looks too much like the registered username ({existing:?})")]
+    UsernameConfusable { username: String, existing: String },
 }
@@ -1,7 +1,8 @@
-use cosmwasm_std::{Addr, coins, Decimal};
+use cosmwasm_std::{Addr, coin, Coin, ContractInfoResponse, CosmosMsg, Empty, from_binary, Order, StdError, StdResult, Storage, Timestamp, to_binary, Uint128, WasmMsg, WasmQuery};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::{
     ensure_eq,
+    Deps,
     DepsMut, 
     Env, 
     MessageInfo, 
@@ -12,17 +13,41 @@ use cw2::{
     set_contract_version,
     get_contract_version
 };
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+use semver::Version;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{GeneralSecurityProfile, MixedScript};
 
 use crate::error::ContractError;
 use crate::msg::{
     ExecuteMsg, 
     InstantiateMsg,
-    MigrateMsg
+    MigrateMsg,
+    Cw20HookMsg,
+    Cw721HookMsg
 };
 use crate::state::{
     AlpineContract,
     DonationInfo,
-    AlpineUser
+    AlpineUser,
+    Config,
+    PendingFeeChange,
+    PauseState,
+    Role,
+    AcceptedDenom,
+    TokenType,
+    UsernameChange,
+    ReleasedUsername,
+    UsernameListing,
+    UsernameTransfer,
+    PatternKind,
+    BlockedPattern,
+    UnavailableReason,
+    username_key,
+    USERNAME_CHANGE_COOLDOWN,
+    RELEASED_USERNAME_GRACE_PERIOD,
+    USERNAME_REGISTRATION_PERIOD
 };
 use crate::traits::{
     DonationExecute
@@ -32,17 +57,54 @@ use crate::traits::{
 const CONTRACT_NAME: &str = "crates.io:alpine-pay";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Fee settings used by deployments which predate the on-chain config
+const LEGACY_FEE_BPS: u64 = 300;
+const LEGACY_FEE_COLLECTOR: &str = "osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a";
+const LEGACY_MAX_FEE_BPS: u64 = 1_000;
+const LEGACY_FEE_NOTICE_PERIOD: u64 = 7 * 24 * 60 * 60;
+const LEGACY_DENOM: &str = "uosmo";
+// The first version which keys usernames by their lowercased name. Deployments migrating from an earlier version have
+// their username index rebuilt
+const LOWERCASE_USERNAMES_VERSION: &str = "0.2.0";
+// The first version which expires usernames. Deployments migrating from an earlier version have every username given
+// an expiry
+const USERNAME_EXPIRY_VERSION: &str = "0.2.0";
+
 impl<'a> AlpineContract<'a> {
     // Instantiate the contract
     pub fn instantiate(
         &self,
         deps: DepsMut,
         _env: Env,
-        _info: MessageInfo,
-        _msg: InstantiateMsg,
+        info: MessageInfo,
+        msg: InstantiateMsg,
     ) -> Result<Response, ContractError> {
         set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+        // Set the owner, defaulting to whoever instantiated the contract
+        let owner = match msg.owner {
+            Some(owner) => validate_address(deps.as_ref(), owner)?,
+            None => info.sender
+        };
+        self.owner.save(deps.storage, &owner)?;
+
+        // Save the fee configuration
+        let config = Config {
+            fee_bps: msg.fee_bps,
+            fee_collector: validate_address(deps.as_ref(), msg.fee_collector)?,
+            max_fee_bps: msg.max_fee_bps,
+            fee_notice_period: msg.fee_notice_period,
+            unicode_usernames: false
+        };
+        config.validate()?;
+        self.config.save(deps.storage, &config)?;
+
+        // Register the denoms which can be donated
+        for accepted_denom in msg.accepted_denoms {
+            validate_accepted_denom(&accepted_denom)?;
+            self.accepted_denoms.save(deps.storage, &accepted_denom.denom, &accepted_denom)?;
+        }
+
         Ok(Response::default())
     }
 
@@ -50,15 +112,90 @@ impl<'a> AlpineContract<'a> {
     pub fn migrate(
         &self,
         deps: DepsMut,
-        _env: Env,
-        _msg: MigrateMsg
+        env: Env,
+        msg: MigrateMsg
     ) -> Result<Response, ContractError> {
         // Verify that the contract name hasn't changed, then set the contract version
         let ver = get_contract_version(deps.storage)?;
         ensure_eq!(ver.contract, CONTRACT_NAME, ContractError::IncorrectContractName { contract_name: String::from(CONTRACT_NAME) });
-        set_contract_version(deps.storage, ver.contract, ver.version.clone())?;
-        
-        Ok(Response::default())
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        // Deployments from before ownership existed are owned by the address given in the message or the legacy fee collector
+        if self.owner.may_load(deps.storage)?.is_none() {
+            let owner = match msg.owner {
+                Some(owner) => validate_address(deps.as_ref(), owner)?,
+                None => Addr::unchecked(LEGACY_FEE_COLLECTOR)
+            };
+            self.owner.save(deps.storage, &owner)?;
+        }
+
+        // Deployments from before the fee config existed keep the fee settings that used to be hardcoded
+        if self.config.may_load(deps.storage)?.is_none() {
+            let config = Config {
+                fee_bps: LEGACY_FEE_BPS,
+                fee_collector: Addr::unchecked(LEGACY_FEE_COLLECTOR),
+                max_fee_bps: LEGACY_MAX_FEE_BPS,
+                fee_notice_period: LEGACY_FEE_NOTICE_PERIOD,
+                unicode_usernames: false
+            };
+            self.config.save(deps.storage, &config)?;
+
+            // These deployments only ever took OSMO, so keep accepting it
+            let osmo = AcceptedDenom {
+                denom: String::from(LEGACY_DENOM),
+                symbol: String::from("OSMO"),
+                decimals: 6,
+                min_amount: Uint128::new(1)
+            };
+            self.accepted_denoms.save(deps.storage, &osmo.denom, &osmo)?;
+        }
+
+        // Rebuild the username index under lowercased keys. Older deployments keyed users by their registered casing,
+        // so two users could differ only in case. The first in key order keeps the name, and the others are
+        // unregistered and reported
+        let mut collisions: Vec<String> = Vec::new();
+        if version_before(&ver.version, LOWERCASE_USERNAMES_VERSION)? {
+            let users: Vec<(String, AlpineUser)> = self.usernames
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<_>>()?;
+            for (key, _) in users.iter() {
+                self.usernames.remove(deps.storage, key.clone());
+            }
+            for (key, user) in users {
+                let canonical = username_key(&key);
+                if self.usernames.has(deps.storage, canonical.clone()) {
+                    if self.addresses.may_load(deps.storage, user.address.clone())?.map_or(false, |u| u.username == user.username) {
+                        self.addresses.remove(deps.storage, user.address);
+                    }
+                    collisions.push(key);
+                    continue;
+                }
+                self.save_user(deps.storage, &user)?;
+            }
+        }
+
+        // Usernames registered before expiry existed get a full registration period from the migration
+        let mut expiry_backfilled_usernames = 0u64;
+        if version_before(&ver.version, USERNAME_EXPIRY_VERSION)? {
+            let unexpiring: Vec<String> = self.usernames
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<_>>()?;
+            for key in unexpiring {
+                if !self.username_expiries.has(deps.storage, key.clone()) {
+                    self.username_expiries.save(deps.storage, key, &env.block.time.plus_seconds(USERNAME_REGISTRATION_PERIOD))?;
+                    expiry_backfilled_usernames += 1;
+                }
+            }
+        }
+
+        // Move donations from the legacy map, which keyed them by string IDs and embedded full users. They were all
+        // charged the legacy fee
+        let migrated_donations = self.migrate_donations(deps.storage, LEGACY_FEE_BPS)?;
+
+        Ok(Response::new()
+            .add_attribute("username_collisions", collisions.join(","))
+            .add_attribute("migrated_donations", migrated_donations.to_string())
+            .add_attribute("expiry_backfilled_usernames", expiry_backfilled_usernames.to_string()))
     }
 
     // Routes the execute messages
@@ -69,49 +206,91 @@ impl<'a> AlpineContract<'a> {
         info: MessageInfo,
         msg: ExecuteMsg,
     ) -> Result<Response, ContractError> {
-        // Either route the message to send_donation or register_user
+        // Reject messages which belong to a paused operation before routing them
+        let pause_state = self.pause_state.may_load(deps.storage)?.unwrap_or_default();
+        ensure_not_paused(&pause_state, &msg)?;
+
         match msg {
             ExecuteMsg::SendDonation { sender, recipient, message } => self.send_donation(deps, _env, info, sender, recipient, message),
+            ExecuteMsg::Receive(cw20_msg) => self.receive_cw20(deps, _env, info, cw20_msg),
+            ExecuteMsg::ReceiveNft(nft_msg) => self.receive_nft(deps, _env, info, nft_msg),
             // With register we can authenticate the user here, whereas with SendDonation it's a bit more complex and done later
             ExecuteMsg::RegisterUser { user, username } => {
                 if info.sender != user.address {
                     return Err(ContractError::InvalidWalletAddress { address: user.address.to_string() })
                 }
                 self.register_user(deps, _env, user, username)
-            }
+            },
+            ExecuteMsg::ChangeUsername { username } => self.change_username(deps, _env, info, username),
+            ExecuteMsg::ListUsername { price } => self.list_username(deps, _env, info, price),
+            ExecuteMsg::DelistUsername { } => self.delist_username(deps, info),
+            ExecuteMsg::BuyUsername { username } => self.buy_username(deps, _env, info, username),
+            ExecuteMsg::TransferUsername { recipient } => self.transfer_username(deps, info, recipient),
+            ExecuteMsg::CancelUsernameTransfer { } => self.cancel_username_transfer(deps, info),
+            ExecuteMsg::AcceptUsernameTransfer { username } => self.accept_username_transfer(deps, _env, info, username),
+            ExecuteMsg::RenewUsername { } => self.renew_username(deps, _env, info),
+            ExecuteMsg::ReserveUsernames { usernames } => self.reserve_usernames(deps, info, usernames),
+            ExecuteMsg::UnreserveUsernames { usernames } => self.unreserve_usernames(deps, info, usernames),
+            ExecuteMsg::BlockPattern { kind, pattern } => self.block_pattern(deps, info, kind, pattern),
+            ExecuteMsg::UnblockPattern { kind, pattern } => self.unblock_pattern(deps, info, kind, pattern),
+            ExecuteMsg::AllocateReservedName { username, address } => self.allocate_reserved_name(deps, _env, info, username, address),
+            ExecuteMsg::SetUnicodeUsernames { enabled } => self.set_unicode_usernames(deps, info, enabled),
+            ExecuteMsg::SetRenewalFee { length, fee } => self.set_renewal_fee(deps, info, length, fee),
+            ExecuteMsg::UpdateConfig { fee_bps, fee_collector } => self.update_config(deps, _env, info, fee_bps, fee_collector),
+            ExecuteMsg::RecoverFunds { recipient, amount } => self.recover_funds(deps, _env, info, recipient, amount),
+            ExecuteMsg::CancelFeeChange { } => self.cancel_fee_change(deps, _env, info),
+            ExecuteMsg::ProposeOwner { new_owner } => self.propose_owner(deps, info, new_owner),
+            ExecuteMsg::AcceptOwnership { } => self.accept_ownership(deps, info),
+            ExecuteMsg::GrantRole { role, address } => self.grant_role(deps, info, role, address),
+            ExecuteMsg::RevokeRole { role, address } => self.revoke_role(deps, info, role, address),
+            ExecuteMsg::SetPaused { donations, registrations, profile_updates } => self.set_paused(deps, info, donations, registrations, profile_updates),
+            ExecuteMsg::SetAcceptedDenom { accepted_denom } => self.set_accepted_denom(deps, info, accepted_denom),
+            ExecuteMsg::RemoveAcceptedDenom { denom } => self.remove_accepted_denom(deps, info, denom),
+            ExecuteMsg::SetAcceptedNftContract { contract_addr } => self.set_accepted_nft_contract(deps, info, contract_addr),
+            ExecuteMsg::RemoveAcceptedNftContract { contract_addr } => self.remove_accepted_nft_contract(deps, info, contract_addr)
         }
     }
 }
 
-impl<'a> DonationExecute for AlpineContract<'a> {
-    // Send a donation to the designated user
-    fn send_donation(
+impl<'a> AlpineContract<'a> {
+    // Validate the funds of a donation before anything is stored. The participants are validated separately
+    pub(crate) fn validate_donation_funds(&self, storage: &dyn Storage, funds: &[Coin]) -> Result<(), ContractError> {
+        // Verify that funds are attached
+        if funds.is_empty() {
+            return Err(ContractError::NoDonation{})
+        }
+
+        // Verify that every coin is an accepted denom and meets the minimum donation
+        for gross in funds.iter() {
+            self.ensure_accepted_coin(storage, gross)?;
+        }
+        Ok(())
+    }
+
+    // Validate the sender, recipient and message of a donation or gift, returning the sender and recipient users.
+    // Users whose usernames have expired at the given time can't be found
+    pub(crate) fn validate_participants(
         &self,
-        deps: DepsMut, 
-        env: Env, 
-        info: MessageInfo,
+        deps: Deps,
+        sender_address: &Addr,
         sender: String,
-        recipient: String, 
-        message: String
-    ) -> Result<Response, ContractError> {
+        recipient: String,
+        message: &str,
+        time: Timestamp
+    ) -> Result<(AlpineUser, AlpineUser), ContractError> {
         // Verify that there's a recipient
         if recipient.is_empty() {
             return Err(ContractError::EmptyUsername {})
         }
 
-        // Verify that funds are attached
-        if info.funds.is_empty() || info.funds[0].amount.to_string() == String::from("0") {
-            return Err(ContractError::NoDonation{})
-        }
-
         // Get an Alpine user for the sender. This technically allows a user to send if they're unregistered
         let sender_user = match sender.is_empty() {
-            true => AlpineUser::new(deps.as_ref(), info.sender.clone(), None)?,
-            false => self.find_alpine_username(deps.storage, sender)?
+            true => AlpineUser::new(deps, sender_address.clone(), None)?,
+            false => self.find_alpine_username(deps.storage, sender, time)?
         };
 
         // Authenticate the sender
-        if info.sender != sender_user.address {
+        if *sender_address != sender_user.address {
             return Err(ContractError::InvalidWalletAddress { address: sender_user.address.to_string() })
         }
 
@@ -121,63 +300,196 @@ impl<'a> DonationExecute for AlpineContract<'a> {
         }
 
         // Find the recipient user by their username
-        let recipient_user = self.find_alpine_username(deps.storage, recipient)?;
-
-        // Build out the donation message
-        let donation = DonationInfo {
-            sender: sender_user,
-            recipient: recipient_user,
-            amount: info.funds,
-            message: message,
-            timestamp: Some(env.block.time)
-        };
+        let recipient_user = self.find_alpine_username(deps.storage, recipient, time)?;
+
+        Ok((sender_user, recipient_user))
+    }
+
+    // Verify that an address can take a username, failing with the reason it can't
+    fn ensure_username_available(
+        &self,
+        storage: &dyn Storage,
+        username: &str,
+        address: &Addr,
+        time: Timestamp
+    ) -> Result<(), ContractError> {
+        let username = username.to_string();
+        match self.username_unavailable_reason(storage, &username, Some(address), time)? {
+            None => Ok(()),
+            Some(UnavailableReason::Reserved) => Err(ContractError::UsernameReserved { username }),
+            Some(UnavailableReason::Blocked { .. }) => Err(ContractError::UsernameBlocked { username }),
+            Some(UnavailableReason::Confusable { username: existing }) => Err(ContractError::UsernameConfusable { username, existing }),
+            Some(_) => Err(ContractError::UsernameNotAvailable { username })
+        }
+    }
+
+    // Hand a username over to an unregistered address. The previous holder is left unregistered, and any listing or
+    // pending transfer of the username is cancelled
+    fn move_username(&self, storage: &mut dyn Storage, user: AlpineUser, to: Addr) -> Result<AlpineUser, ContractError> {
+        if self.addresses.has(storage, to.clone()) {
+            return Err(ContractError::UserAlreadyExists {})
+        }
+
+        let key = username_key(&user.username);
+        self.addresses.remove(storage, user.address);
+        let user = AlpineUser { username: user.username, address: to };
+        self.save_user(storage, &user)?;
+        self.username_listings.remove(storage, key.clone());
+        self.username_transfers.remove(storage, key);
+
+        Ok(user)
+    }
+
+    // Store a validated donation, then split the funds between the recipient and the fee collector
+    fn record_donation(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        mut donation: DonationInfo
+    ) -> Result<Response, ContractError> {
+        // Split every attached coin into the recipient's share and the fee in force at the block time
+        let config = self.effective_config(deps.storage, env.block.time)?;
+        donation.apply_fee(config.fee_bps);
 
         // Update the donations and set the new donation's ID
         let id = self.increment_donations(deps.storage)?;
-        self.donations.update(deps.storage, &id.to_string(), |old| match old {
+        self.donations.update(deps.storage, id, |old| match old {
             Some(_) => Err(ContractError::Unauthorized {}),
             None => Ok(donation.clone())
         })?;
 
-        let total_donation_amount = donation.amount.clone()[0].amount;
-        let donation_fee = Decimal::percent(3) * donation.amount.clone()[0].amount;
-        let recipient_donation = &coins((total_donation_amount - donation_fee).u128(), donation.amount.clone()[0].denom.clone());
-        let commission = &coins(donation_fee.u128(), donation.amount.clone()[0].denom.clone());
+        let splits = &donation.splits;
+        let recipient_donation: Vec<Coin> = splits.iter().map(|s| s.net.clone()).filter(|c| !c.amount.is_zero()).collect();
+        let commission: Vec<Coin> = splits.iter().map(|s| s.fee.clone()).filter(|c| !c.amount.is_zero()).collect();
 
-        // Forward the funds to the relevant wallet address
-        let recipient_bank_msg = BankMsg::Send {
-            to_address: donation.recipient.address.to_string(),
-            amount: recipient_donation.clone()
-        };
+        self.record_totals(deps.storage, id, &donation, &commission)?;
 
-        // Take 3% donation fee to Alpine admin address
-        let fee_bank_msg = BankMsg::Send { 
-            to_address: Addr::unchecked("osmo1zw5337y7a7ajj2jz4t0teyzcy5dup5k8wjz88a").into_string(), 
-            amount: commission.clone()
+        // Forward the funds or NFT to the relevant wallet address, then take the donation fee to the configured fee collector.
+        // NFT gifts don't carry any funds, so there's never a fee on them
+        let mut tx_messages = match &donation.token_type {
+            TokenType::Cw721 { contract_addr, token_id } => vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: donation.recipient.to_string(),
+                    token_id: token_id.clone()
+                })?,
+                funds: vec![]
+            })],
+            token_type => transfer_msgs(token_type, &donation.recipient, &recipient_donation)?
         };
+        tx_messages.append(&mut transfer_msgs(&donation.token_type, &config.fee_collector, &commission)?);
+
+        let mut response = Response::new();
+        if let TokenType::Cw721 { contract_addr, token_id } = &donation.token_type {
+            response = response
+                .add_attribute("nft_contract", contract_addr.to_string())
+                .add_attribute("token_id", token_id.clone());
+        }
 
-        let attributes = vec![("sender_address", donation.sender.address.to_string()), ("sender_username", donation.sender.username.to_string()), 
-                        ("recipient_address", donation.recipient.address.to_string()), ("recipient_username", donation.recipient.username.to_string()),
-                        ("amount", donation.amount[0].amount.to_string()), ("message", donation.message), ("timestamp", env.block.time.to_string()),
+        let attributes = vec![("sender_address", donation.sender.to_string()), ("sender_username", donation.sender_username.clone()), 
+                        ("recipient_address", donation.recipient.to_string()), ("recipient_username", donation.recipient_username.clone()),
+                        ("amount", coins_to_string(&donation.amount)), ("fee", coins_to_string(&commission)),
+                        ("net_amount", coins_to_string(&recipient_donation)), ("message", donation.message), ("timestamp", env.block.time.to_string()),
                         ("id", id.to_string()) ].into_iter();
-        let tx_messages = vec![recipient_bank_msg, fee_bank_msg].into_iter();
 
-        Ok(Response::new().add_messages(tx_messages).add_attributes(attributes))
+        Ok(response.add_messages(tx_messages).add_attributes(attributes))
+    }
+}
+
+impl<'a> DonationExecute for AlpineContract<'a> {
+    // Send a donation to the designated user
+    fn send_donation(
+        &self,
+        deps: DepsMut, 
+        env: Env, 
+        info: MessageInfo,
+        sender: String,
+        recipient: String, 
+        message: String
+    ) -> Result<Response, ContractError> {
+        // Verify that funds are attached. Zero amounts are dropped so they never end up in a bank message
+        let funds: Vec<Coin> = info.funds.iter().filter(|c| !c.amount.is_zero()).cloned().collect();
+
+        self.validate_donation_funds(deps.storage, &funds)?;
+        let (sender_user, recipient_user) = self.validate_participants(deps.as_ref(), &info.sender, sender, recipient, &message, env.block.time)?;
+        let donation = DonationInfo::new(sender_user, recipient_user, funds, message, env.block.time, TokenType::Native);
+        self.record_donation(deps, env, donation)
+    }
+
+    // Receive a donation of CW20 tokens. The token contract calls this when the sender uses Cw20ExecuteMsg::Send
+    fn receive_cw20(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        cw20_msg: Cw20ReceiveMsg
+    ) -> Result<Response, ContractError> {
+        let (sender, recipient, message) = match from_binary(&cw20_msg.msg)? {
+            Cw20HookMsg::SendDonation { sender, recipient, message } => (sender, recipient, message)
+        };
+
+        // The calling contract is the token, and the denom of a CW20 donation is its contract address
+        let token_address = info.sender;
+        let sender_address = validate_address(deps.as_ref(), cw20_msg.sender)?;
+        let funds = match cw20_msg.amount.is_zero() {
+            true => vec![],
+            false => vec![coin(cw20_msg.amount.u128(), token_address.as_str())]
+        };
+
+        self.validate_donation_funds(deps.storage, &funds)?;
+        let (sender_user, recipient_user) = self.validate_participants(deps.as_ref(), &sender_address, sender, recipient, &message, env.block.time)?;
+        let donation = DonationInfo::new(
+            sender_user,
+            recipient_user,
+            funds,
+            message,
+            env.block.time,
+            TokenType::Cw20 { contract_addr: token_address }
+        );
+        self.record_donation(deps, env, donation)
+    }
+
+    // Receive an NFT gift. The NFT contract calls this when the sender uses Cw721ExecuteMsg::SendNft,
+    // and the NFT is forwarded to the recipient's registered address. Only accepted NFT contracts can call this,
+    // since the sender of the gift is whoever the calling contract says it is
+    fn receive_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        nft_msg: Cw721ReceiveMsg
+    ) -> Result<Response, ContractError> {
+        if !self.accepted_nft_contracts.has(deps.storage, &info.sender) {
+            return Err(ContractError::NftContractNotAccepted { contract_addr: info.sender.to_string() })
+        }
+
+        let (sender, recipient, message) = match from_binary(&nft_msg.msg)? {
+            Cw721HookMsg::SendGift { sender, recipient, message } => (sender, recipient, message)
+        };
+
+        let sender_address = validate_address(deps.as_ref(), nft_msg.sender)?;
+        let (sender_user, recipient_user) = self.validate_participants(deps.as_ref(), &sender_address, sender, recipient, &message, env.block.time)?;
+        let donation = DonationInfo::new(
+            sender_user,
+            recipient_user,
+            vec![],
+            message,
+            env.block.time,
+            TokenType::Cw721 { contract_addr: info.sender, token_id: nft_msg.token_id }
+        );
+        self.record_donation(deps, env, donation)
     }
 
     // Register a new Alpine user
     fn register_user(
         &self,
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         mut user: AlpineUser,
         username: String
     ) -> Result<Response, ContractError> {
         // Validate the username
-        let valid_username = match validate_username(username.clone()) {
-            Ok(u) => u,
-            Err(e) => return Err(e)
-        };
+        let valid_username = validate_username(username.clone(), self.unicode_usernames(deps.storage)?)?;
 
         // Verify that the user isn't already registered 
         user = match user.username.is_empty() {
@@ -192,31 +504,788 @@ impl<'a> DonationExecute for AlpineContract<'a> {
             false => return Err(ContractError::UserAlreadyExists {  } )
         };
 
-        // Verify that the desired username isn't already taken
-        let searched_username = match self.usernames.may_load(deps.storage, valid_username.clone()) {
-            Ok(result) => match result {
-                Some(_) => Err(ContractError::UsernameNotAvailable { username: valid_username.clone() }),
-                None => Ok(valid_username.clone())
-            },
-            Err(e) => Err(ContractError::Std(e))
-        }?;
+        // Verify that the desired username isn't already taken, reserved or blocked
+        self.ensure_username_available(deps.storage, &valid_username, &user.address, env.block.time)?;
 
         // Set the user's username, then save them to the contract
-        user.username = searched_username;
-        self.usernames.save(deps.storage, username, &user)?;
-        self.addresses.save(deps.storage, user.address.clone(), &user)?;
+        user.username = valid_username;
+        let expires_at = env.block.time.plus_seconds(USERNAME_REGISTRATION_PERIOD);
+        self.clear_released_username(deps.storage, &username_key(&user.username));
+        self.save_user(deps.storage, &user)?;
+        self.username_expiries.save(deps.storage, username_key(&user.username), &expires_at)?;
         
-        Ok(Response::new().add_attribute("username", user.username))
+        Ok(Response::new()
+            .add_attribute("username", user.username)
+            .add_attribute("expires_at", expires_at.to_string()))
+    }
+
+    // Change the username of the sender. Their old username stays reserved for them for a grace period, and they can't
+    // change their username again until the cooldown has passed. Donations refer to users by address, so they follow
+    // the user to their new username
+    fn change_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String
+    ) -> Result<Response, ContractError> {
+        let valid_username = validate_username(username, self.unicode_usernames(deps.storage)?)?;
+        let mut user = self.get_user_by_address(deps.storage, info.sender)?;
+        if user.username == valid_username {
+            return Err(ContractError::InvalidUsername {
+                username: valid_username,
+                reason: String::from("is already your username")
+            })
+        }
+
+        // Enforce the cooldown since the last username change
+        if let Some(last_change) = self.last_username_change(deps.storage, &user.address)? {
+            let available_at = last_change.changed_at.plus_seconds(USERNAME_CHANGE_COOLDOWN);
+            if env.block.time < available_at {
+                return Err(ContractError::UsernameChangeCooldown { available_at })
+            }
+        }
+
+        // Verify that the desired username isn't already taken, reserved or blocked. Changing the casing of a username
+        // keeps it under the same key
+        self.ensure_username_available(deps.storage, &valid_username, &user.address, env.block.time)?;
+
+        let old_username = user.username.clone();
+        let old_key = username_key(&old_username);
+        let new_key = username_key(&valid_username);
+        let expires_at = self.username_expiries.may_load(deps.storage, old_key.clone())?;
+        if old_key != new_key {
+            self.remove_username(deps.storage, &old_username)?;
+            self.username_listings.remove(deps.storage, old_key.clone());
+            self.username_transfers.remove(deps.storage, old_key.clone());
+            let released = ReleasedUsername {
+                address: user.address.clone(),
+                available_at: env.block.time.plus_seconds(RELEASED_USERNAME_GRACE_PERIOD)
+            };
+            self.release_username(deps.storage, &old_key, &released)?;
+        }
+
+        // The expiry follows the user to their new username, so renaming doesn't renew it
+        user.username = valid_username;
+        self.clear_released_username(deps.storage, &new_key);
+        self.save_user(deps.storage, &user)?;
+        match expires_at {
+            Some(expires_at) => self.username_expiries.save(deps.storage, new_key, &expires_at)?,
+            None => self.username_expiries.remove(deps.storage, new_key)
+        }
+
+        let change = UsernameChange {
+            old_username: old_username.clone(),
+            new_username: user.username.clone(),
+            changed_at: env.block.time
+        };
+        self.username_history.save(deps.storage, (&user.address, env.block.time.nanos()), &change)?;
+
+        Ok(Response::new()
+            .add_attribute("old_username", old_username)
+            .add_attribute("username", user.username))
+    }
+
+    // List the sender's username for sale at a fixed price in an accepted denom. Listing again replaces the price, and
+    // expired usernames can't be listed
+    fn list_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        price: Coin
+    ) -> Result<Response, ContractError> {
+        let user = self.get_user_by_address(deps.storage, info.sender)?;
+        if self.username_expired(deps.storage, &username_key(&user.username), env.block.time)? {
+            return Err(ContractError::UsernameExpired { username: user.username })
+        }
+        if price.amount.is_zero() {
+            return Err(ContractError::InvalidPrice {})
+        }
+        if !self.accepted_denoms.has(deps.storage, &price.denom) {
+            return Err(ContractError::DenomNotAccepted { denom: price.denom })
+        }
+        ensure_native_denom(deps.as_ref(), &price.denom)?;
+
+        let listing = UsernameListing { username: user.username.clone(), seller: user.address, price };
+        self.username_listings.save(deps.storage, username_key(&user.username), &listing)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "list_username")
+            .add_attribute("username", listing.username)
+            .add_attribute("price", listing.price.to_string()))
+    }
+
+    // Take the sender's username off the market
+    fn delist_username(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+        let user = self.get_user_by_address(deps.storage, info.sender)?;
+        let key = username_key(&user.username);
+        if !self.username_listings.has(deps.storage, key.clone()) {
+            return Err(ContractError::UsernameNotListed { username: user.username })
+        }
+        self.username_listings.remove(deps.storage, key);
+
+        Ok(Response::new()
+            .add_attribute("action", "delist_username")
+            .add_attribute("username", user.username))
+    }
+
+    // Buy a listed username by paying exactly its price. The seller is paid the price minus the fee in force, the fee
+    // goes to the fee collector, and the username moves to the buyer. Only unregistered addresses can buy a username
+    fn buy_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String
+    ) -> Result<Response, ContractError> {
+        let key = username_key(&username);
+        let listing = match self.username_listings.may_load(deps.storage, key)? {
+            Some(listing) => listing,
+            None => return Err(ContractError::UsernameNotListed { username })
+        };
+
+        let funds: Vec<Coin> = info.funds.into_iter().filter(|c| !c.amount.is_zero()).collect();
+        if funds != vec![listing.price.clone()] {
+            return Err(ContractError::IncorrectPayment { expected: listing.price.to_string() })
+        }
+
+        let seller = self.find_alpine_username(deps.storage, listing.username.clone(), env.block.time)?;
+        let buyer = self.move_username(deps.storage, seller, info.sender)?;
+
+        let config = self.effective_config(deps.storage, env.block.time)?;
+        let split = config.split(&funds).remove(0);
+        // Bank sends can't be empty, so nothing is sent for a zero fee
+        let messages: Vec<BankMsg> = [(&listing.seller, &split.net), (&config.fee_collector, &split.fee)]
+            .iter()
+            .filter(|(_, amount)| !amount.amount.is_zero())
+            .map(|(to_address, amount)| BankMsg::Send { to_address: to_address.to_string(), amount: vec![(*amount).clone()] })
+            .collect();
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "buy_username")
+            .add_attribute("username", buyer.username)
+            .add_attribute("seller", listing.seller)
+            .add_attribute("buyer", buyer.address)
+            .add_attribute("price", split.gross.to_string())
+            .add_attribute("fee", split.fee.to_string())
+            .add_attribute("net_amount", split.net.to_string()))
+    }
+
+    // Offer the sender's username to another address. The username only moves once the recipient accepts it, and
+    // offering it again replaces the pending transfer
+    fn transfer_username(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        recipient: String
+    ) -> Result<Response, ContractError> {
+        let user = self.get_user_by_address(deps.storage, info.sender)?;
+        let recipient = validate_address(deps.as_ref(), recipient)?;
+        if recipient == user.address {
+            return Err(ContractError::InvalidWalletAddress { address: recipient.to_string() })
+        }
+
+        let transfer = UsernameTransfer { username: user.username.clone(), from: user.address, to: recipient };
+        self.username_transfers.save(deps.storage, username_key(&user.username), &transfer)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "transfer_username")
+            .add_attribute("username", transfer.username)
+            .add_attribute("recipient", transfer.to))
+    }
+
+    // Withdraw the pending transfer of the sender's username
+    fn cancel_username_transfer(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+        let user = self.get_user_by_address(deps.storage, info.sender)?;
+        let key = username_key(&user.username);
+        if !self.username_transfers.has(deps.storage, key.clone()) {
+            return Err(ContractError::NoPendingUsernameTransfer { username: user.username })
+        }
+        self.username_transfers.remove(deps.storage, key);
+
+        Ok(Response::new()
+            .add_attribute("action", "cancel_username_transfer")
+            .add_attribute("username", user.username))
+    }
+
+    // Accept a username offered to the sender. Only unregistered addresses can accept a username, and expired
+    // usernames can't be accepted until they're renewed
+    fn accept_username_transfer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String
+    ) -> Result<Response, ContractError> {
+        let transfer = match self.username_transfers.may_load(deps.storage, username_key(&username))? {
+            Some(transfer) if transfer.to == info.sender => transfer,
+            _ => return Err(ContractError::NoPendingUsernameTransfer { username })
+        };
+
+        let previous = self.find_alpine_username(deps.storage, transfer.username, env.block.time)?;
+        let user = self.move_username(deps.storage, previous, info.sender)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "accept_username_transfer")
+            .add_attribute("username", user.username)
+            .add_attribute("from", transfer.from)
+            .add_attribute("to", user.address))
+    }
+
+    // Renew the sender's username for another registration period by paying exactly its renewal fee, which goes to
+    // the fee collector. A username renewed before its grace period is over is extended from when it expires, and
+    // one renewed after that is extended from the block time, as long as nobody else has taken it. Usernames can't be
+    // renewed until a fee manager sets a fee covering their length
+    fn renew_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+        let user = self.get_user_by_address(deps.storage, info.sender)?;
+        let key = username_key(&user.username);
+
+        let fee = self.renewal_fee(deps.storage, &user.username)?
+            .ok_or_else(|| ContractError::RenewalFeeNotSet { username: user.username.clone() })?;
+        let expected = vec![fee];
+        let funds: Vec<Coin> = info.funds.into_iter().filter(|c| !c.amount.is_zero()).collect();
+        if funds != expected {
+            return Err(ContractError::IncorrectPayment { expected: coins_to_string(&expected) })
+        }
+
+        let renewed_from = match self.username_expiries.may_load(deps.storage, key.clone())? {
+            Some(expires_at) if !self.username_lapsed(deps.storage, &key, env.block.time)? => expires_at,
+            _ => env.block.time
+        };
+        let expires_at = renewed_from.plus_seconds(USERNAME_REGISTRATION_PERIOD);
+        self.username_expiries.save(deps.storage, key, &expires_at)?;
+
+        let config = self.effective_config(deps.storage, env.block.time)?;
+        Ok(Response::new()
+            .add_message(BankMsg::Send { to_address: config.fee_collector.to_string(), amount: expected.clone() })
+            .add_attribute("action", "renew_username")
+            .add_attribute("username", user.username)
+            .add_attribute("fee", coins_to_string(&expected))
+            .add_attribute("expires_at", expires_at.to_string()))
     }
+
+    // Reserve usernames so that only a verifier can allocate them. Only a moderator can do this, and usernames which
+    // are already registered stay with their holders
+    fn reserve_usernames(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        usernames: Vec<String>
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Moderator)?;
+        let unicode = self.unicode_usernames(deps.storage)?;
+        for username in usernames.iter() {
+            let valid_username = validate_username(username.clone(), unicode)?;
+            self.reserve_username(deps.storage, &username_key(&valid_username))?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "reserve_usernames")
+            .add_attribute("usernames", usernames.join(",")))
+    }
+
+    // Release reserved usernames so that anybody can register them. Only a moderator can do this
+    fn unreserve_usernames(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        usernames: Vec<String>
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Moderator)?;
+        for username in usernames.iter() {
+            self.unreserve_username(deps.storage, &username_key(username));
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "unreserve_usernames")
+            .add_attribute("usernames", usernames.join(",")))
+    }
+
+    // Block usernames which match a pattern from being registered. Only a moderator can do this, and usernames which
+    // are already registered stay with their holders
+    fn block_pattern(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        kind: PatternKind,
+        pattern: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Moderator)?;
+        if pattern.is_empty() {
+            return Err(ContractError::InvalidPattern { pattern })
+        }
+
+        let pattern = BlockedPattern { kind, pattern: username_key(&pattern) };
+        self.blocked_patterns.save(deps.storage, (kind.as_str(), &pattern.pattern), &pattern)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "block_pattern")
+            .add_attribute("kind", kind.as_str())
+            .add_attribute("pattern", pattern.pattern))
+    }
+
+    // Stop blocking a pattern. Only a moderator can do this
+    fn unblock_pattern(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        kind: PatternKind,
+        pattern: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Moderator)?;
+        let pattern = username_key(&pattern);
+        if !self.blocked_patterns.has(deps.storage, (kind.as_str(), &pattern)) {
+            return Err(ContractError::InvalidPattern { pattern })
+        }
+        self.blocked_patterns.remove(deps.storage, (kind.as_str(), &pattern));
+
+        Ok(Response::new()
+            .add_attribute("action", "unblock_pattern")
+            .add_attribute("kind", kind.as_str())
+            .add_attribute("pattern", pattern))
+    }
+
+    // Register a reserved username to a verified creator's address, which can't already be registered. Only a
+    // verifier can do this. The username is no longer reserved once it's allocated, and blocked patterns don't apply
+    fn allocate_reserved_name(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String,
+        address: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Verifier)?;
+        let address = validate_address(deps.as_ref(), address)?;
+        let valid_username = validate_username(username, self.unicode_usernames(deps.storage)?)?;
+        let key = username_key(&valid_username);
+        if !self.reserved_usernames.has(deps.storage, key.clone()) {
+            return Err(ContractError::UsernameNotReserved { username: valid_username })
+        }
+        if self.addresses.has(deps.storage, address.clone()) {
+            return Err(ContractError::UserAlreadyExists {})
+        }
+        match self.username_unavailable_reason(deps.storage, &valid_username, Some(&address), env.block.time)? {
+            None | Some(UnavailableReason::Reserved) | Some(UnavailableReason::Blocked { .. }) => (),
+            Some(_) => return Err(ContractError::UsernameNotAvailable { username: valid_username })
+        }
+
+        let user = AlpineUser { username: valid_username, address };
+        self.unreserve_username(deps.storage, &key);
+        self.clear_released_username(deps.storage, &key);
+        self.save_user(deps.storage, &user)?;
+        self.username_expiries.save(deps.storage, key, &env.block.time.plus_seconds(USERNAME_REGISTRATION_PERIOD))?;
+
+        Ok(Response::new()
+            .add_attribute("action", "allocate_reserved_name")
+            .add_attribute("username", user.username)
+            .add_attribute("address", user.address))
+    }
+
+    // Allow or disallow usernames which use letters from any script. Only a moderator can do this, and usernames
+    // which are already registered are kept either way
+    fn set_unicode_usernames(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        enabled: bool
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Moderator)?;
+        let mut config = self.config.load(deps.storage)?;
+        config.unicode_usernames = enabled;
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_unicode_usernames")
+            .add_attribute("unicode_usernames", enabled.to_string()))
+    }
+
+    // Set the annual renewal fee of usernames up to the given length in an accepted denom, or remove it when no fee
+    // is given. Only a fee manager can do this
+    fn set_renewal_fee(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        length: u32,
+        fee: Option<Coin>
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+        let response = Response::new()
+            .add_attribute("action", "set_renewal_fee")
+            .add_attribute("length", length.to_string());
+
+        match fee {
+            Some(fee) => {
+                if fee.amount.is_zero() {
+                    return Err(ContractError::InvalidPrice {})
+                }
+                if !self.accepted_denoms.has(deps.storage, &fee.denom) {
+                    return Err(ContractError::DenomNotAccepted { denom: fee.denom })
+                }
+                ensure_native_denom(deps.as_ref(), &fee.denom)?;
+                self.renewal_fees.save(deps.storage, length, &fee)?;
+                Ok(response.add_attribute("fee", fee.to_string()))
+            },
+            None => {
+                self.renewal_fees.remove(deps.storage, length);
+                Ok(response.add_attribute("fee", ""))
+            }
+        }
+    }
+
+    // Propose a fee and/or fee collector change. Only a fee manager can do this, and the maximum fee can't be changed.
+    // The change is scheduled as a pending change which takes effect after the notice period.
+    // Proposing a new change replaces the one which is pending
+    fn update_config(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        fee_bps: Option<u64>,
+        fee_collector: Option<String>
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+        let config = self.settle_fee_change(deps.storage, env.block.time)?;
+        let mut response = Response::new().add_attribute("action", "update_config");
+
+        if fee_bps.is_some() || fee_collector.is_some() {
+            let pending = PendingFeeChange {
+                fee_bps: fee_bps.unwrap_or(config.fee_bps),
+                fee_collector: match fee_collector {
+                    Some(fee_collector) => validate_address(deps.as_ref(), fee_collector)?,
+                    None => config.fee_collector.clone()
+                },
+                effective_at: env.block.time.plus_seconds(config.fee_notice_period)
+            };
+            Config { fee_bps: pending.fee_bps, ..config }.validate()?;
+            self.pending_fee_change.save(deps.storage, &pending)?;
+
+            response = response
+                .add_attribute("pending_fee_bps", pending.fee_bps.to_string())
+                .add_attribute("pending_fee_collector", pending.fee_collector)
+                .add_attribute("effective_at", pending.effective_at.to_string());
+        }
+
+        Ok(response)
+    }
+
+    // Withdraw a fee change before it takes effect. Only a fee manager can do this
+    fn cancel_fee_change(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+        self.settle_fee_change(deps.storage, env.block.time)?;
+        if self.pending_fee_change.may_load(deps.storage)?.is_none() {
+            return Err(ContractError::NoPendingFeeChange {})
+        }
+        self.pending_fee_change.remove(deps.storage);
+
+        Ok(Response::new().add_attribute("action", "cancel_fee_change"))
+    }
+
+    // Send funds which are stuck in the contract to the given address. Only the owner can do this.
+    // If no amount is given, the entire native balance of the contract is recovered
+    fn recover_funds(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        amount: Option<Vec<Coin>>
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Owner)?;
+        let recipient = validate_address(deps.as_ref(), recipient)?;
+
+        let amount = match amount {
+            Some(amount) => amount.into_iter().filter(|c| !c.amount.is_zero()).collect(),
+            None => deps.querier.query_all_balances(env.contract.address)?
+        };
+        if amount.is_empty() {
+            return Err(ContractError::NoFundsToRecover {})
+        }
+
+        Ok(Response::new()
+            .add_message(BankMsg::Send { to_address: recipient.to_string(), amount: amount.clone() })
+            .add_attribute("action", "recover_funds")
+            .add_attribute("recipient", recipient)
+            .add_attribute("amount", coins_to_string(&amount)))
+    }
+
+    // Nominate a new owner. Ownership only moves once the new owner accepts it
+    fn propose_owner(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        new_owner: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Owner)?;
+        let new_owner = validate_address(deps.as_ref(), new_owner)?;
+        self.pending_owner.save(deps.storage, &new_owner)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "propose_owner")
+            .add_attribute("pending_owner", new_owner))
+    }
+
+    // Accept a pending ownership transfer. Only the nominated address can do this
+    fn accept_ownership(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response, ContractError> {
+        let pending_owner = match self.pending_owner.may_load(deps.storage)? {
+            Some(pending_owner) => pending_owner,
+            None => return Err(ContractError::NoPendingOwner {})
+        };
+        if info.sender != pending_owner {
+            return Err(ContractError::Unauthorized {})
+        }
+        self.owner.save(deps.storage, &pending_owner)?;
+        self.pending_owner.remove(deps.storage);
+
+        Ok(Response::new()
+            .add_attribute("action", "accept_ownership")
+            .add_attribute("owner", pending_owner))
+    }
+
+    // Grant a role to an address. Only the owner can do this
+    fn grant_role(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        role: Role,
+        address: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Owner)?;
+        if role == Role::Owner {
+            return Err(ContractError::InvalidRole { role: role.as_str().to_string() })
+        }
+        let address = validate_address(deps.as_ref(), address)?;
+        self.roles.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+
+        Ok(Response::new()
+            .add_attribute("action", "grant_role")
+            .add_attribute("role", role.as_str())
+            .add_attribute("address", address))
+    }
+
+    // Revoke a role from an address. Only the owner can do this
+    fn revoke_role(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        role: Role,
+        address: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Owner)?;
+        let address = validate_address(deps.as_ref(), address)?;
+        if !self.roles.has(deps.storage, (role.as_str(), &address)) {
+            return Err(ContractError::RoleNotHeld { role: role.as_str().to_string(), address: address.to_string() })
+        }
+        self.roles.remove(deps.storage, (role.as_str(), &address));
+
+        Ok(Response::new()
+            .add_attribute("action", "revoke_role")
+            .add_attribute("role", role.as_str())
+            .add_attribute("address", address))
+    }
+
+    // Pause or unpause donations, registrations and profile updates. Only a pauser can do this
+    fn set_paused(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        donations: Option<bool>,
+        registrations: Option<bool>,
+        profile_updates: Option<bool>
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::Pauser)?;
+        let mut pause_state = self.pause_state.may_load(deps.storage)?.unwrap_or_default();
+        pause_state.donations = donations.unwrap_or(pause_state.donations);
+        pause_state.registrations = registrations.unwrap_or(pause_state.registrations);
+        pause_state.profile_updates = profile_updates.unwrap_or(pause_state.profile_updates);
+        self.pause_state.save(deps.storage, &pause_state)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_paused")
+            .add_attribute("donations", pause_state.donations.to_string())
+            .add_attribute("registrations", pause_state.registrations.to_string())
+            .add_attribute("profile_updates", pause_state.profile_updates.to_string()))
+    }
+
+    // Add a denom to the accepted denoms, or update its display data and minimum. Only a fee manager can do this
+    fn set_accepted_denom(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        accepted_denom: AcceptedDenom
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+        validate_accepted_denom(&accepted_denom)?;
+        self.accepted_denoms.save(deps.storage, &accepted_denom.denom, &accepted_denom)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_accepted_denom")
+            .add_attribute("denom", accepted_denom.denom)
+            .add_attribute("min_amount", accepted_denom.min_amount))
+    }
+
+    // Stop accepting a denom for donations. Only a fee manager can do this
+    fn remove_accepted_denom(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        denom: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+        if !self.accepted_denoms.has(deps.storage, &denom) {
+            return Err(ContractError::DenomNotAccepted { denom })
+        }
+        self.accepted_denoms.remove(deps.storage, &denom);
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_accepted_denom")
+            .add_attribute("denom", denom))
+    }
+
+    // Accept NFT gifts from a CW721 contract. Only a fee manager can do this
+    fn set_accepted_nft_contract(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+        let contract_addr = validate_address(deps.as_ref(), contract_addr)?;
+        self.accepted_nft_contracts.save(deps.storage, &contract_addr, &Empty {})?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_accepted_nft_contract")
+            .add_attribute("contract_addr", contract_addr))
+    }
+
+    // Stop accepting NFT gifts from a CW721 contract. Only a fee manager can do this
+    fn remove_accepted_nft_contract(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String
+    ) -> Result<Response, ContractError> {
+        self.ensure_role(deps.storage, &info.sender, Role::FeeManager)?;
+        let contract_addr = Addr::unchecked(contract_addr);
+        if !self.accepted_nft_contracts.has(deps.storage, &contract_addr) {
+            return Err(ContractError::NftContractNotAccepted { contract_addr: contract_addr.to_string() })
+        }
+        self.accepted_nft_contracts.remove(deps.storage, &contract_addr);
+
+        Ok(Response::new()
+            .add_attribute("action", "remove_accepted_nft_contract")
+            .add_attribute("contract_addr", contract_addr))
+    }
+}
+
+// Check whether a stored contract version is older than the given one
+fn version_before(version: &str, since: &str) -> StdResult<bool> {
+    let parse = |version: &str| Version::parse(version).map_err(|e| StdError::generic_err(format!("Invalid version {} - {}", version, e)));
+    Ok(parse(version)? < parse(since)?)
 }
 
-// Validate that the user's username is accepted
-fn validate_username(username: String) -> Result<String, ContractError> {
+// Validate an entry for the accepted denoms
+fn validate_accepted_denom(accepted_denom: &AcceptedDenom) -> Result<(), ContractError> {
+    if accepted_denom.denom.is_empty() || accepted_denom.symbol.is_empty() {
+        return Err(ContractError::InvalidDenom { denom: accepted_denom.denom.clone() })
+    }
+    Ok(())
+}
+
+// Reject a message if the operation it belongs to is paused. Admin messages can't be paused
+fn ensure_not_paused(pause_state: &PauseState, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    let operation = match msg {
+        ExecuteMsg::SendDonation { .. } | ExecuteMsg::Receive(_) | ExecuteMsg::ReceiveNft(_) if pause_state.donations => "donations",
+        ExecuteMsg::RegisterUser { .. } if pause_state.registrations => "registrations",
+        ExecuteMsg::ChangeUsername { .. }
+        | ExecuteMsg::ListUsername { .. }
+        | ExecuteMsg::DelistUsername { }
+        | ExecuteMsg::BuyUsername { .. }
+        | ExecuteMsg::TransferUsername { .. }
+        | ExecuteMsg::CancelUsernameTransfer { }
+        | ExecuteMsg::AcceptUsernameTransfer { .. }
+        | ExecuteMsg::RenewUsername { } if pause_state.profile_updates => "profile_updates",
+        _ => return Ok(())
+    };
+    Err(ContractError::Paused { operation: String::from(operation) })
+}
+
+// Build the messages which transfer funds of the given token type. Bank sends can't be empty, so nothing is sent
+// for an empty list. CW20 coins are denominated in their token contract address
+fn transfer_msgs(token_type: &TokenType, to_address: &Addr, amount: &[Coin]) -> StdResult<Vec<CosmosMsg>> {
+    if amount.is_empty() {
+        return Ok(vec![])
+    }
+    match token_type {
+        TokenType::Native => Ok(vec![CosmosMsg::Bank(BankMsg::Send {
+            to_address: to_address.to_string(),
+            amount: amount.to_vec()
+        })]),
+        TokenType::Cw20 { contract_addr } => amount.iter().map(|c| Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: to_address.to_string(), amount: c.amount })?,
+            funds: vec![]
+        }))).collect(),
+        // NFTs don't have a fungible amount to transfer
+        TokenType::Cw721 { .. } => Ok(vec![])
+    }
+}
+
+// Format a list of coins for use in an attribute, e.g. "970uosmo,97uion"
+fn coins_to_string(coins: &[Coin]) -> String {
+    coins.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(",")
+}
+
+// Validate a wallet address supplied in a message
+// Verify that a denom is native rather than a CW20 token, which is accepted under its contract address. Usernames are
+// paid for with the funds sent along with the message, so CW20 tokens can't pay for them
+fn ensure_native_denom(deps: Deps, denom: &str) -> Result<(), ContractError> {
+    let query = WasmQuery::ContractInfo { contract_addr: denom.to_string() };
+    match deps.querier.query::<ContractInfoResponse>(&query.into()) {
+        Ok(_) => Err(ContractError::NativeDenomRequired { denom: denom.to_string() }),
+        Err(_) => Ok(())
+    }
+}
+
+fn validate_address(deps: Deps, address: String) -> Result<Addr, ContractError> {
+    deps.api.addr_validate(&address).map_err(|_| ContractError::InvalidWalletAddress { address })
+}
+
+// Validate that the user's username is accepted, returning the form it's stored in
+pub(crate) fn validate_username(username: String, unicode: bool) -> Result<String, ContractError> {
     // Users can't register with an empty username.
     if username.is_empty() {
         return Err(ContractError::EmptyUsername {})
     }
 
+    if unicode {
+        return validate_unicode_username(username)
+    }
+
     // Users can't create a name with more than 32 characters
     if username.len() > 32 {
         return Err(ContractError::InvalidUsername { 
@@ -237,3 +1306,36 @@ fn validate_username(username: String) -> Result<String, ContractError> {
 
     Ok(username)
 }
+
+// Validate a username when usernames can use any script. The username is NFKC-normalized so that compatibility forms of
+// characters are stored the same way, and it has to be written in a single script so that it can't mix lookalike letters
+fn validate_unicode_username(username: String) -> Result<String, ContractError> {
+    let normalized: String = username.nfkc().collect();
+
+    // Users can't create a name with more than 32 characters
+    if normalized.chars().count() > 32 {
+        return Err(ContractError::InvalidUsername {
+            username,
+            reason: String::from("must be shorter than 33 characters")
+        })
+    }
+
+    // Only letters and digits which are safe in identifiers are allowed, along with dashes and underscores
+    for c in normalized.chars() {
+        if !(c == '-' || c == '_' || (c.is_alphanumeric() && c.identifier_allowed())) {
+            return Err(ContractError::InvalidUsername {
+                username,
+                reason: String::from("only letters, numbers, underscores, and dashes are allowed")
+            })
+        }
+    }
+
+    if !normalized.as_str().is_single_script() {
+        return Err(ContractError::InvalidUsername {
+            username,
+            reason: String::from("must be written in a single script")
+        })
+    }
+
+    Ok(normalized)
+}
@@ -4,6 +4,7 @@ pub mod state;
 pub mod traits;
 pub mod execute;
 pub mod query;
+mod migrations;
 pub mod contract_tests;
 
 pub use crate::error::ContractError;
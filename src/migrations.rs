@@ -0,0 +1,101 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    Coin,
+    Order,
+    StdError,
+    StdResult,
+    Storage,
+    Timestamp
+};
+use cw_storage_plus::{
+    Index,
+    IndexList,
+    IndexedMap,
+    MultiIndex
+};
+
+use crate::state::{
+    AlpineContract,
+    AlpineUser,
+    DonationInfo,
+    TokenType
+};
+
+// Donations used to embed full copies of the sender and recipient
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub(crate) struct LegacyDonationInfo {
+    pub sender: AlpineUser,
+    pub recipient: AlpineUser,
+    pub amount: Vec<Coin>,
+    pub message: String,
+    pub timestamp: Option<Timestamp>
+}
+
+impl From<LegacyDonationInfo> for DonationInfo {
+    fn from(legacy: LegacyDonationInfo) -> DonationInfo {
+        DonationInfo {
+            sender: legacy.sender.address,
+            sender_username: legacy.sender.username,
+            recipient: legacy.recipient.address,
+            recipient_username: legacy.recipient.username,
+            amount: legacy.amount,
+            message: legacy.message,
+            timestamp: legacy.timestamp,
+            token_type: TokenType::Native,
+            splits: vec![],
+            fee_bps: 0
+        }
+    }
+}
+
+// The legacy donations were indexed by the sender and recipient users, which were keyed by their address
+pub(crate) struct LegacyDonationIndexes<'a> {
+    pub sender: MultiIndex<'a, AlpineUser, LegacyDonationInfo, Vec<u8>>,
+    pub recipient: MultiIndex<'a, AlpineUser, LegacyDonationInfo, Vec<u8>>
+}
+
+impl<'a> IndexList<LegacyDonationInfo> for LegacyDonationIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item=&'_ dyn Index<LegacyDonationInfo>> + '_> {
+        let v: Vec<&dyn Index<LegacyDonationInfo>> = vec![&self.sender, &self.recipient];
+        Box::new(v.into_iter())
+    }
+}
+
+// The original donations map, keyed by the donation ID as a string. Ranges over it were lexicographic rather than chronological
+pub(crate) fn legacy_donations<'a>() -> IndexedMap<'a, &'a str, LegacyDonationInfo, LegacyDonationIndexes<'a>> {
+    let indexes = LegacyDonationIndexes {
+        sender: MultiIndex::new(|d| d.sender.clone(), "donations", "donations__sender"),
+        recipient: MultiIndex::new(|d| d.recipient.clone(), "donations", "donations__recipient")
+    };
+    IndexedMap::new("donations", indexes)
+}
+
+impl<'a> AlpineContract<'a> {
+    // Move donations out of the legacy map and into the current one, which builds their indexes. Every donation gets
+    // the fee it was charged recorded on it, and is added to the running totals, leaderboards and daily statistics.
+    // Returns the number of donations moved, which is zero once a deployment has been migrated
+    pub(crate) fn migrate_donations(&self, storage: &mut dyn Storage, fee_bps: u64) -> StdResult<u64> {
+        let legacy = legacy_donations();
+        let donations: Vec<(String, LegacyDonationInfo)> = legacy
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+        let migrated = donations.len() as u64;
+
+        for (key, donation) in donations {
+            let id: u64 = key
+                .parse()
+                .map_err(|_| StdError::generic_err(format!("Invalid donation ID {}", key)))?;
+            legacy.remove(storage, &key)?;
+
+            let mut donation: DonationInfo = donation.into();
+            donation.apply_fee(fee_bps);
+            self.donations.save(storage, id, &donation)?;
+            let fees: Vec<Coin> = donation.splits.iter().map(|s| s.fee.clone()).filter(|c| !c.amount.is_zero()).collect();
+            self.record_totals(storage, id, &donation, &fees)?;
+        }
+
+        Ok(migrated)
+    }
+}
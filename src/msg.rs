@@ -1,45 +1,210 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use crate::state::{DonationInfo, AlpineUser};
-use cosmwasm_std::{Addr};
+use crate::state::{
+    AlpineUser,
+    Config,
+    DonationInfo,
+    PendingFeeChange,
+    PauseState,
+    Role,
+    AcceptedDenom,
+    TokenType,
+    UserTotals,
+    LargestDonation,
+    PlatformTotals,
+    DailyStats,
+    CoinSplit,
+    UsernameChange,
+    UsernameListing,
+    UsernameTransfer,
+    PatternKind,
+    BlockedPattern,
+    UnavailableReason
+};
+use cosmwasm_std::{Addr, Coin, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
 
+// The owner defaults to the instantiating address when it isn't provided
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg { }
+pub struct InstantiateMsg {
+    pub owner: Option<String>,
+    pub fee_bps: u64,
+    pub fee_collector: String,
+    pub max_fee_bps: u64,
+    pub fee_notice_period: u64,
+    pub accepted_denoms: Vec<AcceptedDenom>
+}
 
+// Deployments without a config are migrated to the legacy fee settings and only accept OSMO. The owner defaults to the legacy fee collector
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg { }
+pub struct MigrateMsg {
+    pub owner: Option<String>
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     SendDonation { sender: String, recipient: String, message: String },
-    RegisterUser { user: AlpineUser, username: String }
+    Receive(Cw20ReceiveMsg),
+    ReceiveNft(Cw721ReceiveMsg),
+    RegisterUser { user: AlpineUser, username: String },
+    ChangeUsername { username: String },
+    ListUsername { price: Coin },
+    DelistUsername { },
+    BuyUsername { username: String },
+    TransferUsername { recipient: String },
+    CancelUsernameTransfer { },
+    AcceptUsernameTransfer { username: String },
+    RenewUsername { },
+    ReserveUsernames { usernames: Vec<String> },
+    UnreserveUsernames { usernames: Vec<String> },
+    BlockPattern { kind: PatternKind, pattern: String },
+    UnblockPattern { kind: PatternKind, pattern: String },
+    AllocateReservedName { username: String, address: String },
+    SetUnicodeUsernames { enabled: bool },
+    SetRenewalFee { length: u32, fee: Option<Coin> },
+    UpdateConfig { fee_bps: Option<u64>, fee_collector: Option<String> },
+    RecoverFunds { recipient: String, amount: Option<Vec<Coin>> },
+    CancelFeeChange { },
+    ProposeOwner { new_owner: String },
+    AcceptOwnership { },
+    GrantRole { role: Role, address: String },
+    RevokeRole { role: Role, address: String },
+    SetPaused { donations: Option<bool>, registrations: Option<bool>, profile_updates: Option<bool> },
+    SetAcceptedDenom { accepted_denom: AcceptedDenom },
+    RemoveAcceptedDenom { denom: String },
+    SetAcceptedNftContract { contract_addr: String },
+    RemoveAcceptedNftContract { contract_addr: String }
+}
+
+// The message embedded in a Cw20ReceiveMsg when donating CW20 tokens
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    SendDonation { sender: String, recipient: String, message: String }
+}
+
+// The message embedded in a Cw721ReceiveMsg when gifting an NFT
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721HookMsg {
+    SendGift { sender: String, recipient: String, message: String }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetSentDonations{ sender: String },
-    GetReceivedDonations { recipient: String },
+    GetSentDonations{ sender: String, start_after: Option<u64>, limit: Option<u32> },
+    GetReceivedDonations { recipient: String, start_after: Option<u64>, limit: Option<u32> },
     GetDonationCount {  },
+    GetDonation { id: u64 },
+    GetDonationsBetween { sender: String, recipient: String, start_after: Option<u64>, limit: Option<u32> },
+    SearchDonations { filter: DonationFilter, start_after: Option<u64>, limit: Option<u32> },
+    GetUserTotals { username: String },
+    GetUniqueDonors { username: String },
+    GetLargestDonation { username: String, denom: String },
+    GetPlatformTotals { },
+    GetTopSupporters { recipient: String, denom: String, limit: Option<u32> },
+    GetDailyStats { recipient: Option<String>, from: Timestamp, to: Timestamp, limit: Option<u32> },
+    GetStatement { recipient: String, from: Timestamp, to: Timestamp, start_after: Option<StatementCursor>, limit: Option<u32> },
+    SimulateDonation { sender: String, recipient: String, message: Option<String>, funds: Vec<Coin> },
     IsUsernameAvailable { username: String },
-    GetAllUsers { },
+    GetAllUsers { start_after: Option<String>, limit: Option<u32> },
     GetUserByAddr { address: Addr },
-    GetUserByName { username: String }
+    GetUserByName { username: String },
+    GetUsernameHistory { address: Addr },
+    GetUsernameListings { start_after: Option<String>, limit: Option<u32> },
+    GetUsernameTransfer { username: String },
+    GetReservedUsernames { start_after: Option<String>, limit: Option<u32> },
+    GetBlockedPatterns { },
+    GetUsernameExpiry { username: String },
+    GetRenewalFees { },
+    GetConfig { },
+    GetPendingFeeChange { },
+    GetOwnership { },
+    GetRoleHolders { role: Role },
+    GetPauseState { },
+    GetAcceptedDenoms { },
+    GetAcceptedNftContracts { }
+}
+
+// Whether to search the donations a user sent or the donations they received
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DonationDirection {
+    Sent,
+    Received
+}
+
+// Filters for searching a user's donations. The time bounds are inclusive. The amount bounds apply to coins of the denom,
+// or to any coin when there's no denom, and a donation matches if one of its coins does
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DonationFilter {
+    pub username: String,
+    pub direction: DonationDirection,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    pub denom: Option<String>,
+    pub min_amount: Option<Uint128>,
+    pub max_amount: Option<Uint128>
 }
 
-// Return a list of donation IDs mapped to the data stored in the donation
+impl DonationFilter {
+    // Check a donation against the denom and amount filters. The time filters are applied by the index range. NFT gifts
+    // have no amount, so they only match when there are no denom or amount filters
+    pub fn matches(&self, donation: &DonationInfo) -> bool {
+        if self.denom.is_none() && self.min_amount.is_none() && self.max_amount.is_none() {
+            return true
+        }
+        donation.amount.iter().any(|c| {
+            self.denom.as_ref().map_or(true, |denom| c.denom == *denom)
+                && self.min_amount.map_or(true, |min| c.amount >= min)
+                && self.max_amount.map_or(true, |max| c.amount <= max)
+        })
+    }
+}
+
+// Return a page of donation IDs mapped to the data stored in the donation, and the ID to start the next page after
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MultiDonationResponse{
-    pub donations: Vec<(Vec<u8>, DonationInfo)>
+    pub donations: Vec<(u64, DonationView)>,
+    pub next_cursor: Option<u64>
+}
+
+// Return a single donation and its ID
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DonationResponse{
+    pub id: u64,
+    pub donation: DonationView
+}
+
+// A donation with its sender and recipient resolved to their current users, along with the usernames they had
+// when the donation was sent and the fee taken from every coin
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DonationView {
+    pub sender: AlpineUser,
+    pub recipient: AlpineUser,
+    pub sender_username_at_donation: String,
+    pub recipient_username_at_donation: String,
+    pub amount: Vec<Coin>,
+    pub message: String,
+    pub timestamp: Option<Timestamp>,
+    pub token_type: TokenType,
+    pub splits: Vec<CoinSplit>,
+    pub fee_bps: u64
 }
 
-// Return a list of Alpine users
+// Return a page of Alpine users, and the username to start the next page after
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MultiUserResponse{
-    pub users: Vec<AlpineUser>
+    pub users: Vec<AlpineUser>,
+    pub next_cursor: Option<String>
 }
 
 // Return the count of all donations in the contract
@@ -49,11 +214,12 @@ pub struct DonationCountResponse{
     pub count: u64
 }
 
-// Return whether the queried username is available
+// Return whether the queried username is available, and why it isn't when it's unavailable
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct UsernameAvailableResponse {
-    pub is_available: bool
+    pub is_available: bool,
+    pub reason: Option<UnavailableReason>
 }
 
 // Returns a single Alpine user
@@ -61,4 +227,244 @@ pub struct UsernameAvailableResponse {
 #[serde(rename_all = "snake_case")]
 pub struct AlpineUserResponse{
     pub user: AlpineUser,
-}
\ No newline at end of file
+}
+
+// Returns every username change of an address, oldest first
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UsernameHistoryResponse{
+    pub changes: Vec<UsernameChange>,
+}
+
+// Return a page of usernames listed for sale, and the username to start the next page after
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UsernameListingsResponse{
+    pub listings: Vec<UsernameListing>,
+    pub next_cursor: Option<String>
+}
+
+// Returns the pending transfer of a username, if there is one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UsernameTransferResponse{
+    pub transfer: Option<UsernameTransfer>,
+}
+
+// Return a page of reserved usernames, and the username to start the next page after
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReservedUsernamesResponse{
+    pub usernames: Vec<String>,
+    pub next_cursor: Option<String>
+}
+
+// Returns every blocked pattern
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BlockedPatternsResponse{
+    pub patterns: Vec<BlockedPattern>,
+}
+
+// Returns when a username expires, when its grace period ends and what renewing it costs. Usernames without an expiry
+// never expire
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UsernameExpiryResponse{
+    pub username: String,
+    pub expires_at: Option<Timestamp>,
+    pub grace_ends_at: Option<Timestamp>,
+    pub is_expired: bool,
+    pub renewal_fee: Option<Coin>
+}
+
+// A renewal fee, which applies to usernames up to the given length
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RenewalFee{
+    pub length: u32,
+    pub fee: Coin
+}
+
+// Returns every renewal fee, ordered by length
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RenewalFeesResponse{
+    pub fees: Vec<RenewalFee>,
+}
+
+// Returns the fee configuration in force at the current block time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfigResponse{
+    pub config: Config,
+}
+
+// Returns the fee change which hasn't taken effect yet, if there is one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingFeeChangeResponse{
+    pub pending: Option<PendingFeeChange>,
+}
+
+// Returns the owner and the nominated owner, if there is one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OwnershipResponse{
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+}
+
+// Returns every address which holds a role
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RoleHoldersResponse{
+    pub role: Role,
+    pub holders: Vec<Addr>,
+}
+
+// Returns which operations are paused
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PauseStateResponse{
+    pub pause_state: PauseState,
+}
+
+// Returns every denom which can be donated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AcceptedDenomsResponse{
+    pub denoms: Vec<AcceptedDenom>,
+}
+
+// Returns every CW721 contract whose NFTs can be gifted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AcceptedNftContractsResponse{
+    pub contracts: Vec<Addr>,
+}
+
+// Return what a user has sent and received in every denom they've used
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UserTotalsResponse {
+    pub totals: Vec<UserTotals>
+}
+
+// Return the number of different donors a user has received from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UniqueDonorsResponse {
+    pub count: u64
+}
+
+// Return the largest donation a user has received in a denom, if they've received any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct LargestDonationResponse {
+    pub largest: Option<LargestDonation>
+}
+
+// Return the donation volume and fees in every denom that's been donated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PlatformTotalsResponse {
+    pub totals: Vec<PlatformTotals>
+}
+
+// A donor on a leaderboard, resolved to their current user, and everything they've given before fees
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopSupporter {
+    pub user: AlpineUser,
+    pub amount: Uint128
+}
+
+// Return a recipient's donors in one denom, from the most given to the least
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopSupportersResponse {
+    pub supporters: Vec<TopSupporter>
+}
+
+// Return the daily donation statistics in a time range, oldest first. Days without donations are left out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DailyStatsResponse {
+    pub buckets: Vec<DailyStats>
+}
+
+// The version of the statement format. It changes whenever a field is added, removed or changes meaning
+pub const STATEMENT_VERSION: u32 = 1;
+
+// One coin of a donation on a statement. The timestamp is in nanoseconds, and the amounts are in the smallest unit of the denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StatementEntry {
+    pub id: u64,
+    pub timestamp: Timestamp,
+    pub denom: String,
+    pub gross: Uint128,
+    pub fee: Uint128,
+    pub net: Uint128
+}
+
+// Everything received in one denom over a statement period, and the number of donations it came from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StatementTotal {
+    pub denom: String,
+    pub gross: Uint128,
+    pub fee: Uint128,
+    pub net: Uint128,
+    pub count: u64
+}
+
+impl StatementTotal {
+    pub fn new(denom: String) -> StatementTotal {
+        StatementTotal { denom, gross: Uint128::zero(), fee: Uint128::zero(), net: Uint128::zero(), count: 0 }
+    }
+
+    // Add one coin of a donation to the totals
+    pub fn add(&mut self, split: &CoinSplit) {
+        self.gross += split.gross.amount;
+        self.fee += split.fee.amount;
+        self.net += split.net.amount;
+        self.count += 1;
+    }
+}
+
+// Where the next page of a statement starts, and the totals of every page before it. Pass it back unchanged to get the
+// next page
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StatementCursor {
+    pub id: u64,
+    pub totals: Vec<StatementTotal>
+}
+
+// Return a page of a recipient's statement for a period, and the cursor to start the next page after. The totals for
+// the whole period are only returned with the last page, once every page has been added to them. The time bounds
+// are inclusive
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StatementResponse {
+    pub version: u32,
+    pub recipient: Addr,
+    pub from: Timestamp,
+    pub to: Timestamp,
+    pub entries: Vec<StatementEntry>,
+    pub totals: Option<Vec<StatementTotal>>,
+    pub next_cursor: Option<StatementCursor>
+}
+
+// Return how a donation would be split between the recipient and the fee collector. If sending the donation would fail,
+// the error it would fail with is returned instead and there are no splits
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SimulateDonationResponse {
+    pub splits: Vec<CoinSplit>,
+    pub fee_bps: Option<u64>,
+    pub fee_collector: Option<Addr>,
+    pub error: Option<String>
+}
@@ -7,20 +7,69 @@ use cosmwasm_std::{
     Order, 
     to_binary,
     Addr,
-    Timestamp
+    StdError,
+    Timestamp,
+    Coin
 };
+use cw_storage_plus::Bound;
 
 use crate::msg::{
     QueryMsg, 
     MultiDonationResponse, 
+    DonationResponse,
+    DonationView,
+    DonationFilter,
+    DonationDirection,
     UsernameAvailableResponse,
     MultiUserResponse,
     AlpineUserResponse, 
-    DonationCountResponse
+    UsernameHistoryResponse,
+    UsernameListingsResponse,
+    UsernameTransferResponse,
+    ReservedUsernamesResponse,
+    BlockedPatternsResponse,
+    UsernameExpiryResponse,
+    RenewalFee,
+    RenewalFeesResponse,
+    DonationCountResponse,
+    ConfigResponse,
+    PendingFeeChangeResponse,
+    OwnershipResponse,
+    RoleHoldersResponse,
+    PauseStateResponse,
+    AcceptedDenomsResponse,
+    AcceptedNftContractsResponse,
+    UserTotalsResponse,
+    UniqueDonorsResponse,
+    LargestDonationResponse,
+    PlatformTotalsResponse,
+    TopSupporter,
+    TopSupportersResponse,
+    DailyStatsResponse,
+    StatementEntry,
+    StatementTotal,
+    StatementResponse,
+    StatementCursor,
+    STATEMENT_VERSION,
+    SimulateDonationResponse
 };
-use crate::state::{ AlpineContract, AlpineUser, DonationInfo };
+use crate::state::{ AlpineContract, AlpineUser, CoinSplit, Config, DonationInfo, Role, UnavailableReason, username_key, day_of, EXPIRED_USERNAME_GRACE_PERIOD };
+use crate::execute::validate_username;
+use crate::ContractError;
 use crate::traits::DonationQuery;
 
+// The number of items list queries return when no limit is given, and the most they'll return
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// Statistics are returned a day per bucket, so their limits cover a month by default and a year at most
+const DEFAULT_STATS_LIMIT: u32 = 31;
+const MAX_STATS_LIMIT: u32 = 366;
+
+// Statements are paged by donation, in larger pages than other lists since they're meant for exporting
+const DEFAULT_STATEMENT_LIMIT: u32 = 100;
+const MAX_STATEMENT_LIMIT: u32 = 500;
+
 impl<'a> DonationQuery for AlpineContract<'a>
 {
     // Get a count of all the donations
@@ -29,54 +78,319 @@ impl<'a> DonationQuery for AlpineContract<'a>
         Ok(DonationCountResponse { count })
     }
 
-    // Get all of the donations sent by a user
-    fn get_sent_donations(&self, deps: Deps, sender: String) -> StdResult<MultiDonationResponse> {
-        let sender_user = self.find_alpine_username(deps.storage, sender).unwrap();
+    // Get a page of the donations sent by a user, oldest first
+    fn get_sent_donations(
+        &self,
+        deps: Deps,
+        sender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse> {
+        let sender_user = self.find_user(deps, sender)?;
 
-        // Generate a vector of tuples containing the donation and a byte array identifier.
-        let donations: StdResult<Vec<(Vec<_>, _)>> = self
+        // Generate a vector of tuples containing the donation ID and the donation, in ID order
+        let start = start_after.map(Bound::exclusive);
+        let donations = self
             .donations
             .idx
             .sender
-            .prefix(sender_user)
-            .range(deps.storage, None, None, Order::Ascending)
-            .collect();
-        let donations = sort_donations_by_date(donations?.clone());
-
-        Ok(MultiDonationResponse{ donations })
+            .prefix(sender_user.address)
+            .range(deps.storage, start, None, Order::Ascending);
+        self.paginate_donations(deps, donations, limit)
     }
 
-    // Get all of the donations received by a user
-    fn get_received_donations(&self, deps: Deps, recipient: String) -> StdResult<MultiDonationResponse> {
-        let recipient_user = self.find_alpine_username(deps.storage, recipient).unwrap();
+    // Get a page of the donations received by a user, oldest first
+    fn get_received_donations(
+        &self,
+        deps: Deps,
+        recipient: String,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse> {
+        let recipient_user = self.find_user(deps, recipient)?;
 
-        // Generate a vector of tuples containing the donation and a byte array identifier
-        let donations: StdResult<Vec<(Vec<_>, _)>> = self
+        // Generate a vector of tuples containing the donation ID and the donation, in ID order
+        let start = start_after.map(Bound::exclusive);
+        let donations = self
             .donations
             .idx
             .recipient
-            .prefix(recipient_user)
+            .prefix(recipient_user.address)
+            .range(deps.storage, start, None, Order::Ascending);
+        self.paginate_donations(deps, donations, limit)
+    }
+
+    // Get a single donation by its ID
+    fn get_donation(&self, deps: Deps, id: u64) -> StdResult<DonationResponse> {
+        let donation = self.donations.load(deps.storage, id)?;
+        let donation = self.donation_view(deps, donation)?;
+        Ok(DonationResponse { id, donation })
+    }
+
+    // Get a page of the donations one user has sent another, oldest first
+    fn get_donations_between(
+        &self,
+        deps: Deps,
+        sender: String,
+        recipient: String,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse> {
+        let sender_user = self.find_user(deps, sender)?;
+        let recipient_user = self.find_user(deps, recipient)?;
+
+        let start = start_after.map(Bound::exclusive);
+        let donations = self
+            .donations
+            .idx
+            .pair
+            .prefix((sender_user.address, recipient_user.address))
+            .range(deps.storage, start, None, Order::Ascending);
+        self.paginate_donations(deps, donations, limit)
+    }
+
+    // Search a user's sent or received donations, oldest first. The time bounds narrow the index range that's scanned,
+    // and the denom and amount filters are checked on each donation in it
+    fn search_donations(
+        &self,
+        deps: Deps,
+        filter: DonationFilter,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse> {
+        let user = self.find_user(deps, filter.username.clone())?;
+        let index = match filter.direction {
+            DonationDirection::Sent => &self.donations.idx.sender_time,
+            DonationDirection::Received => &self.donations.idx.recipient_time
+        };
+
+        // Resume after the cursor donation, which is already past the start of the time range
+        let start = match start_after {
+            Some(id) => Bound::exclusive((self.donations.load(deps.storage, id)?.time_key(), id)),
+            None => Bound::inclusive((filter.from.map_or(0, |t| t.nanos()), 0))
+        };
+        let end = Bound::inclusive((filter.to.map_or(u64::MAX, |t| t.nanos()), u64::MAX));
+
+        let donations = index
+            .sub_prefix(user.address)
+            .range(deps.storage, Some(start), Some(end), Order::Ascending)
+            .filter(move |item| item.as_ref().map_or(true, |(_, donation)| filter.matches(donation)));
+        self.paginate_donations(deps, Box::new(donations), limit)
+    }
+
+    // Get what a user has sent and received in every denom they've used
+    fn get_user_totals(&self, deps: Deps, username: String) -> StdResult<UserTotalsResponse> {
+        let user = self.find_user(deps, username)?;
+        let totals = self
+            .user_totals
+            .prefix(&user.address)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, totals)| totals))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(UserTotalsResponse { totals })
+    }
+
+    // Get the number of different donors a user has received from
+    fn get_unique_donors(&self, deps: Deps, username: String) -> StdResult<UniqueDonorsResponse> {
+        let user = self.find_user(deps, username)?;
+        let count = self.unique_donors.may_load(deps.storage, &user.address)?.unwrap_or_default();
+        Ok(UniqueDonorsResponse { count })
+    }
+
+    // Get the largest donation a user has received in a denom
+    fn get_largest_donation(&self, deps: Deps, username: String, denom: String) -> StdResult<LargestDonationResponse> {
+        let user = self.find_user(deps, username)?;
+        let largest = self.largest_donations.may_load(deps.storage, (&user.address, &denom))?;
+        Ok(LargestDonationResponse { largest })
+    }
+
+    // Get the donation volume and fees in every denom that's been donated
+    fn get_platform_totals(&self, deps: Deps) -> StdResult<PlatformTotalsResponse> {
+        let totals = self
+            .platform_totals
             .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, totals)| totals))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(PlatformTotalsResponse { totals })
+    }
+
+    // Get a recipient's top donors in a denom, from the most given to the least
+    fn get_top_supporters(
+        &self,
+        deps: Deps,
+        recipient: String,
+        denom: String,
+        limit: Option<u32>
+    ) -> StdResult<TopSupportersResponse> {
+        let recipient_user = self.find_user(deps, recipient)?;
+        let supporters = self
+            .supporter_ranks
+            .prefix((&recipient_user.address, &denom))
+            .range(deps.storage, None, None, Order::Descending)
+            .take(page_limit(limit))
+            .map(|item| {
+                let (_, supporter) = item?;
+                Ok(TopSupporter {
+                    user: self.resolve_user(deps.storage, supporter.address)?,
+                    amount: supporter.amount
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(TopSupportersResponse { supporters })
+    }
+
+    // Get the daily statistics for a creator, or for the whole platform when there's no recipient, between two times
+    fn get_daily_stats(
+        &self,
+        deps: Deps,
+        recipient: Option<String>,
+        from: Timestamp,
+        to: Timestamp,
+        limit: Option<u32>
+    ) -> StdResult<DailyStatsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_STATS_LIMIT).min(MAX_STATS_LIMIT) as usize;
+        let start = Some(Bound::inclusive(day_of(from)));
+        let end = Some(Bound::inclusive(day_of(to)));
+
+        let buckets = match recipient {
+            Some(recipient) => {
+                let recipient_user = self.find_user(deps, recipient)?;
+                self.creator_daily_stats
+                    .prefix(&recipient_user.address)
+                    .range(deps.storage, start, end, Order::Ascending)
+                    .take(limit)
+                    .map(|item| item.map(|(_, stats)| stats))
+                    .collect::<StdResult<Vec<_>>>()?
+            },
+            None => self.platform_daily_stats
+                .range(deps.storage, start, end, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, stats)| stats))
+                .collect::<StdResult<Vec<_>>>()?
+        };
+        Ok(DailyStatsResponse { buckets })
+    }
+
+    // Get a recipient's statement for a period, with a line for every coin they received and the totals for the whole
+    // period. The lines are paged by donation, and the totals are the same on every page
+    fn get_statement(
+        &self,
+        deps: Deps,
+        recipient: String,
+        from: Timestamp,
+        to: Timestamp,
+        start_after: Option<StatementCursor>,
+        limit: Option<u32>
+    ) -> StdResult<StatementResponse> {
+        let recipient_user = self.find_user(deps, recipient)?;
+        let index = self.donations.idx.recipient_time.sub_prefix(recipient_user.address.clone());
+        let end = Bound::inclusive((to.nanos(), u64::MAX));
+
+        // Resume after the cursor donation, which is already past the start of the period, carrying on from the totals
+        // of the pages before it
+        let (start, mut totals) = match start_after {
+            Some(cursor) => {
+                let time_key = self.donations.load(deps.storage, cursor.id)?.time_key();
+                (Bound::exclusive((time_key, cursor.id)), cursor.totals)
+            },
+            None => (Bound::inclusive((from.nanos(), 0)), vec![])
+        };
+        let limit = limit.unwrap_or(DEFAULT_STATEMENT_LIMIT).min(MAX_STATEMENT_LIMIT) as usize;
+
+        // One extra is loaded to tell if there's another page
+        let mut donations = index
+            .range(deps.storage, Some(start), Some(end), Order::Ascending)
+            .take(limit + 1)
+            .collect::<StdResult<Vec<_>>>()?;
+        let last_page = donations.len() <= limit;
+        donations.truncate(limit);
+
+        for split in donations.iter().flat_map(|(_, donation)| donation.splits.iter()) {
+            match totals.iter_mut().find(|t| t.denom == split.gross.denom) {
+                Some(total) => total.add(split),
+                None => {
+                    let mut total = StatementTotal::new(split.gross.denom.clone());
+                    total.add(split);
+                    totals.push(total);
+                }
+            }
+        }
+        let (totals, next_cursor) = match (last_page, donations.last()) {
+            (false, Some((id, _))) => (None, Some(StatementCursor { id: *id, totals })),
+            _ => (Some(totals), None)
+        };
+
+        let entries = donations
+            .into_iter()
+            .flat_map(|(id, donation)| {
+                let timestamp = Timestamp::from_nanos(donation.time_key());
+                donation.splits.into_iter().map(move |split| StatementEntry {
+                    id,
+                    timestamp,
+                    denom: split.gross.denom,
+                    gross: split.gross.amount,
+                    fee: split.fee.amount,
+                    net: split.net.amount
+                })
+            })
             .collect();
-        let donations = sort_donations_by_date(donations?.clone());
 
-        Ok(MultiDonationResponse{ donations })
+        Ok(StatementResponse {
+            version: STATEMENT_VERSION,
+            recipient: recipient_user.address,
+            from,
+            to,
+            entries,
+            totals,
+            next_cursor
+        })
+    }
+
+    // Run a donation from an address through the same checks and fee split as sending it, without storing anything.
+    // The sender donates as the user registered to their address, or anonymously if they aren't registered
+    fn simulate_donation(
+        &self,
+        deps: Deps,
+        env: Env,
+        sender: String,
+        recipient: String,
+        message: Option<String>,
+        funds: Vec<Coin>
+    ) -> StdResult<SimulateDonationResponse> {
+        let response = match self.split_donation(deps, env, sender, recipient, message, funds) {
+            Ok((splits, config)) => SimulateDonationResponse {
+                splits,
+                fee_bps: Some(config.fee_bps),
+                fee_collector: Some(config.fee_collector),
+                error: None
+            },
+            Err(e) => SimulateDonationResponse {
+                splits: vec![],
+                fee_bps: None,
+                fee_collector: None,
+                error: Some(e.to_string())
+            }
+        };
+        Ok(response)
     }
 
     // Check if a username has already been registered
-    fn is_username_available(&self, deps: Deps, username: String) -> StdResult<UsernameAvailableResponse> {
-        let is_available = !self.contains_username(deps.storage, username);
-        Ok(UsernameAvailableResponse { is_available })
+    fn is_username_available(&self, deps: Deps, env: Env, username: String) -> StdResult<UsernameAvailableResponse> {
+        let reason = match validate_username(username.clone(), self.unicode_usernames(deps.storage)?) {
+            Ok(_) => self.username_unavailable_reason(deps.storage, &username, None, env.block.time)?,
+            Err(e) => Some(UnavailableReason::Invalid { reason: e.to_string() })
+        };
+        Ok(UsernameAvailableResponse { is_available: reason.is_none(), reason })
     }
     
-    // Get a list of all registered users
-    fn get_all_users(&self, deps: Deps) -> StdResult<MultiUserResponse> {
+    // Get a page of registered users, ordered by username
+    fn get_all_users(&self, deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<MultiUserResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.map(|username| Bound::exclusive(username_key(&username)));
+
         // Get a list of usernames mapped to their corresponding user
-        let usernames: StdResult<Vec<(String, _)>> = self
-            .usernames
-            .prefix_range(deps.storage, None, None, Order::Ascending)
-            .collect();
-        let usernames = usernames?;
+        let usernames = self.usernames.range(deps.storage, start, None, Order::Ascending);
+        let (usernames, next_cursor) = paginate(usernames, limit, |(username, _)| username.clone())?;
 
         // Remove the Alpine user from the vector above, returning just a list of usernames
         let mut users: Vec<AlpineUser> = Vec::new();
@@ -84,7 +398,7 @@ impl<'a> DonationQuery for AlpineContract<'a>
             users.push(username.1);
         }
 
-        Ok(MultiUserResponse{ users })
+        Ok(MultiUserResponse{ users, next_cursor })
     }
 
     // Find the corresponding Alpine user for a given wallet address
@@ -96,45 +410,302 @@ impl<'a> DonationQuery for AlpineContract<'a>
         Ok(AlpineUserResponse{ user })
     }
     
-    // Find the corresponding Alpine user for a given username
-    fn get_user_by_name(&self, deps: Deps, username: String) -> StdResult<AlpineUserResponse> {
-        let user = match self.find_alpine_username(deps.storage, username.clone()) {
+    // Find the corresponding Alpine user for a given username. Expired usernames aren't found
+    fn get_user_by_name(&self, deps: Deps, env: Env, username: String) -> StdResult<AlpineUserResponse> {
+        let user = match self.find_alpine_username(deps.storage, username, env.block.time) {
             Ok(user) => { user },
             Err(_) => { AlpineUser::empty() }
         };
 
         Ok(AlpineUserResponse { user })
     }
+
+    // Get every username change of an address, oldest first
+    fn get_username_history(&self, deps: Deps, address: Addr) -> StdResult<UsernameHistoryResponse> {
+        let changes = self
+            .username_history
+            .prefix(&address)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, change)| change))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(UsernameHistoryResponse { changes })
+    }
+
+    // Get a page of the usernames listed for sale, ordered by username. Listings of expired usernames are left out of
+    // the page after it's read, since they can't be bought, so a page can have fewer listings than the limit even
+    // when there are more after it
+    fn get_username_listings(&self, deps: Deps, env: Env, start_after: Option<String>, limit: Option<u32>) -> StdResult<UsernameListingsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.map(|username| Bound::exclusive(username_key(&username)));
+
+        let listings = self.username_listings.range(deps.storage, start, None, Order::Ascending);
+        let (page, next_cursor) = paginate(listings, limit, |(key, _)| key.clone())?;
+        let mut listings = Vec::new();
+        for (key, listing) in page {
+            if !self.username_expired(deps.storage, &key, env.block.time)? {
+                listings.push(listing);
+            }
+        }
+
+        Ok(UsernameListingsResponse { listings, next_cursor })
+    }
+
+    // Get a page of the reserved usernames, ordered by their lowercased username
+    fn get_reserved_usernames(&self, deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<ReservedUsernamesResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.map(|username| Bound::exclusive(username_key(&username)));
+
+        let usernames = self.reserved_usernames.keys(deps.storage, start, None, Order::Ascending);
+        let (usernames, next_cursor) = paginate(usernames, limit, |username| username.clone())?;
+
+        Ok(ReservedUsernamesResponse { usernames, next_cursor })
+    }
+
+    // Get every blocked pattern, ordered by kind
+    fn get_blocked_patterns(&self, deps: Deps) -> StdResult<BlockedPatternsResponse> {
+        let patterns = self
+            .blocked_patterns
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, pattern)| pattern))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(BlockedPatternsResponse { patterns })
+    }
+
+    // Get when a username expires and what renewing it costs
+    fn get_username_expiry(&self, deps: Deps, env: Env, username: String) -> StdResult<UsernameExpiryResponse> {
+        let key = username_key(&username);
+        let username = match self.usernames.may_load(deps.storage, key.clone())? {
+            Some(user) => user.username,
+            None => return Err(StdError::generic_err(ContractError::UserNotFound { user: username }.to_string()))
+        };
+        let expires_at = self.username_expiries.may_load(deps.storage, key.clone())?;
+        let grace_ends_at = expires_at.map(|expires_at| expires_at.plus_seconds(EXPIRED_USERNAME_GRACE_PERIOD));
+        let is_expired = self.username_expired(deps.storage, &key, env.block.time)?;
+        let renewal_fee = self.renewal_fee(deps.storage, &username)?;
+        Ok(UsernameExpiryResponse { username, expires_at, grace_ends_at, is_expired, renewal_fee })
+    }
+
+    // Get every renewal fee, ordered by the length it applies up to
+    fn get_renewal_fees(&self, deps: Deps) -> StdResult<RenewalFeesResponse> {
+        let fees = self
+            .renewal_fees
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(length, fee)| RenewalFee { length, fee }))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(RenewalFeesResponse { fees })
+    }
+
+    // Get the pending transfer of a username, if it's been offered to another address
+    fn get_username_transfer(&self, deps: Deps, username: String) -> StdResult<UsernameTransferResponse> {
+        let transfer = self.username_transfers.may_load(deps.storage, username_key(&username))?;
+        Ok(UsernameTransferResponse { transfer })
+    }
+
+    // Get the fee configuration in force at the current block time
+    fn get_config(&self, deps: Deps, env: Env) -> StdResult<ConfigResponse> {
+        let config = self.effective_config(deps.storage, env.block.time)?;
+        Ok(ConfigResponse { config })
+    }
+
+    // Get the proposed fee change and when it takes effect. Changes which are already in force aren't pending
+    fn get_pending_fee_change(&self, deps: Deps, env: Env) -> StdResult<PendingFeeChangeResponse> {
+        let pending = self.pending_fee_change
+            .may_load(deps.storage)?
+            .filter(|p| !p.is_effective(env.block.time));
+        Ok(PendingFeeChangeResponse { pending })
+    }
+
+    // Get the owner and the nominated owner, if there is one
+    fn get_ownership(&self, deps: Deps) -> StdResult<OwnershipResponse> {
+        let owner = self.owner.load(deps.storage)?;
+        let pending_owner = self.pending_owner.may_load(deps.storage)?;
+        Ok(OwnershipResponse { owner, pending_owner })
+    }
+
+    // Get every address which holds a role. The owner is the only holder of Role::Owner
+    fn get_role_holders(&self, deps: Deps, role: Role) -> StdResult<RoleHoldersResponse> {
+        let holders = match role {
+            Role::Owner => vec![self.owner.load(deps.storage)?],
+            _ => self
+                .roles
+                .prefix(role.as_str())
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<Addr>>>()?
+        };
+        Ok(RoleHoldersResponse { role, holders })
+    }
+
+    // Get which operations are paused
+    fn get_pause_state(&self, deps: Deps) -> StdResult<PauseStateResponse> {
+        let pause_state = self.pause_state.may_load(deps.storage)?.unwrap_or_default();
+        Ok(PauseStateResponse { pause_state })
+    }
+
+    // Get every denom which can be donated, so that the frontend can offer them
+    fn get_accepted_denoms(&self, deps: Deps) -> StdResult<AcceptedDenomsResponse> {
+        let denoms = self
+            .accepted_denoms
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, accepted_denom)| accepted_denom))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(AcceptedDenomsResponse { denoms })
+    }
+
+    // Get every CW721 contract whose NFTs can be gifted
+    fn get_accepted_nft_contracts(&self, deps: Deps) -> StdResult<AcceptedNftContractsResponse> {
+        let contracts = self
+            .accepted_nft_contracts
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(AcceptedNftContractsResponse { contracts })
+    }
 }
 
 // Route queries to the smart contract
 impl<'a> AlpineContract<'a> {
-    pub fn query(&self, deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    pub fn query(&self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         match msg {
-            QueryMsg::GetSentDonations{ sender } => to_binary(&self.get_sent_donations(deps, sender)?),
-            QueryMsg::GetReceivedDonations { recipient } => to_binary(&self.get_received_donations(deps, recipient)?),
+            QueryMsg::GetSentDonations{ sender, start_after, limit } => {
+                to_binary(&self.get_sent_donations(deps, sender, start_after, limit)?)
+            },
+            QueryMsg::GetReceivedDonations { recipient, start_after, limit } => {
+                to_binary(&self.get_received_donations(deps, recipient, start_after, limit)?)
+            },
             QueryMsg::GetDonationCount {  } => to_binary(&self.get_donation_count(deps)?),
-            QueryMsg::IsUsernameAvailable { username } => to_binary(&self.is_username_available(deps, username)?),
-            QueryMsg::GetAllUsers { } => to_binary(&self.get_all_users(deps)?),
+            QueryMsg::GetDonation { id } => to_binary(&self.get_donation(deps, id)?),
+            QueryMsg::GetDonationsBetween { sender, recipient, start_after, limit } => {
+                to_binary(&self.get_donations_between(deps, sender, recipient, start_after, limit)?)
+            },
+            QueryMsg::SearchDonations { filter, start_after, limit } => {
+                to_binary(&self.search_donations(deps, filter, start_after, limit)?)
+            },
+            QueryMsg::GetUserTotals { username } => to_binary(&self.get_user_totals(deps, username)?),
+            QueryMsg::GetUniqueDonors { username } => to_binary(&self.get_unique_donors(deps, username)?),
+            QueryMsg::GetLargestDonation { username, denom } => to_binary(&self.get_largest_donation(deps, username, denom)?),
+            QueryMsg::GetPlatformTotals { } => to_binary(&self.get_platform_totals(deps)?),
+            QueryMsg::GetTopSupporters { recipient, denom, limit } => {
+                to_binary(&self.get_top_supporters(deps, recipient, denom, limit)?)
+            },
+            QueryMsg::GetDailyStats { recipient, from, to, limit } => {
+                to_binary(&self.get_daily_stats(deps, recipient, from, to, limit)?)
+            },
+            QueryMsg::GetStatement { recipient, from, to, start_after, limit } => {
+                to_binary(&self.get_statement(deps, recipient, from, to, start_after, limit)?)
+            },
+            QueryMsg::SimulateDonation { sender, recipient, message, funds } => {
+                to_binary(&self.simulate_donation(deps, env, sender, recipient, message, funds)?)
+            },
+            QueryMsg::IsUsernameAvailable { username } => to_binary(&self.is_username_available(deps, env, username)?),
+            QueryMsg::GetAllUsers { start_after, limit } => to_binary(&self.get_all_users(deps, start_after, limit)?),
             QueryMsg::GetUserByAddr { address } => to_binary(&self.get_user_by_addr(deps, address)?),
-            QueryMsg::GetUserByName { username } => to_binary(&self.get_user_by_name(deps, username)?)
+            QueryMsg::GetUserByName { username } => to_binary(&self.get_user_by_name(deps, env, username)?),
+            QueryMsg::GetUsernameHistory { address } => to_binary(&self.get_username_history(deps, address)?),
+            QueryMsg::GetUsernameListings { start_after, limit } => to_binary(&self.get_username_listings(deps, env, start_after, limit)?),
+            QueryMsg::GetUsernameTransfer { username } => to_binary(&self.get_username_transfer(deps, username)?),
+            QueryMsg::GetReservedUsernames { start_after, limit } => to_binary(&self.get_reserved_usernames(deps, start_after, limit)?),
+            QueryMsg::GetBlockedPatterns { } => to_binary(&self.get_blocked_patterns(deps)?),
+            QueryMsg::GetUsernameExpiry { username } => to_binary(&self.get_username_expiry(deps, env, username)?),
+            QueryMsg::GetRenewalFees { } => to_binary(&self.get_renewal_fees(deps)?),
+            QueryMsg::GetConfig { } => to_binary(&self.get_config(deps, env)?),
+            QueryMsg::GetPendingFeeChange { } => to_binary(&self.get_pending_fee_change(deps, env)?),
+            QueryMsg::GetOwnership { } => to_binary(&self.get_ownership(deps)?),
+            QueryMsg::GetRoleHolders { role } => to_binary(&self.get_role_holders(deps, role)?),
+            QueryMsg::GetPauseState { } => to_binary(&self.get_pause_state(deps)?),
+            QueryMsg::GetAcceptedDenoms { } => to_binary(&self.get_accepted_denoms(deps)?),
+            QueryMsg::GetAcceptedNftContracts { } => to_binary(&self.get_accepted_nft_contracts(deps)?)
         }
     }
 }
 
-// Sort donations providing the most recent donation first. This is only used on the backend - not directly reachable
-fn sort_donations_by_date(mut donations: Vec<(Vec<u8>, DonationInfo)>) -> Vec<(Vec<u8>, DonationInfo)>{
-    donations.sort_by(|a, b| {
-        let a_timestamp = match a.1.timestamp{
-            Some(time) => time,
-            None => Timestamp::from_seconds(0)
-        };
-        let b_timestamp = match b.1.timestamp{
-            Some(time) => time,
-            None => Timestamp::from_seconds(0)
-        };
+impl<'a> AlpineContract<'a> {
+    // Find a registered user by their username, turning a missing user into a query error. Expired usernames are
+    // still found, so that their donation history stays visible
+    fn find_user(&self, deps: Deps, username: String) -> StdResult<AlpineUser> {
+        match self.usernames.may_load(deps.storage, username_key(&username))? {
+            Some(user) => Ok(user),
+            None => Err(StdError::generic_err(ContractError::UserNotFound { user: username }.to_string()))
+        }
+    }
+
+    // Validate a simulated donation like send_donation does, then split it with the fee in force at the block time
+    fn split_donation(
+        &self,
+        deps: Deps,
+        env: Env,
+        sender: String,
+        recipient: String,
+        message: Option<String>,
+        funds: Vec<Coin>
+    ) -> Result<(Vec<CoinSplit>, Config), ContractError> {
+        if self.pause_state.may_load(deps.storage)?.unwrap_or_default().donations {
+            return Err(ContractError::Paused { operation: String::from("donations") })
+        }
 
-        a_timestamp.cmp(&b_timestamp)
-    });
-    return donations;
-}
\ No newline at end of file
+        let sender_address = deps.api.addr_validate(&sender)
+            .map_err(|_| ContractError::InvalidWalletAddress { address: sender })?;
+        let sender_user = self.resolve_user(deps.storage, sender_address.clone())?;
+        let funds: Vec<Coin> = funds.into_iter().filter(|c| !c.amount.is_zero()).collect();
+        let message = message.unwrap_or_default();
+        self.validate_donation_funds(deps.storage, &funds)?;
+        self.validate_participants(deps, &sender_address, sender_user.username, recipient, &message, env.block.time)?;
+
+        let config = self.effective_config(deps.storage, env.block.time)?;
+        Ok((config.split(&funds), config))
+    }
+
+    // Take a page of donations from a range, with the ID to start the next page after if there are more
+    fn paginate_donations(
+        &self,
+        deps: Deps,
+        donations: Box<dyn Iterator<Item = StdResult<(u64, DonationInfo)>> + '_>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse> {
+        let (donations, next_cursor) = paginate(donations, page_limit(limit), |(id, _)| *id)?;
+
+        let donations = donations
+            .into_iter()
+            .map(|(id, donation)| Ok((id, self.donation_view(deps, donation)?)))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(MultiDonationResponse { donations, next_cursor })
+    }
+
+    // Resolve the sender and recipient of a donation to the users currently registered to their addresses
+    fn donation_view(&self, deps: Deps, donation: DonationInfo) -> StdResult<DonationView> {
+        Ok(DonationView {
+            sender: self.resolve_user(deps.storage, donation.sender)?,
+            recipient: self.resolve_user(deps.storage, donation.recipient)?,
+            sender_username_at_donation: donation.sender_username,
+            recipient_username_at_donation: donation.recipient_username,
+            amount: donation.amount,
+            message: donation.message,
+            timestamp: donation.timestamp,
+            token_type: donation.token_type,
+            splits: donation.splits,
+            fee_bps: donation.fee_bps
+        })
+    }
+}
+
+// Clamp the number of items a list query returns
+fn page_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+// Take a page of items from a range, along with the cursor of its last item if there's another page. One extra item is
+// loaded to tell if there is
+fn paginate<T, C>(
+    items: impl Iterator<Item = StdResult<T>>,
+    limit: usize,
+    cursor: impl Fn(&T) -> C
+) -> StdResult<(Vec<T>, Option<C>)> {
+    let mut items = items.take(limit + 1).collect::<StdResult<Vec<_>>>()?;
+    let next_cursor = match items.len() > limit {
+        true => {
+            items.truncate(limit);
+            items.last().map(cursor)
+        },
+        false => None
+    };
+    Ok((items, next_cursor))
+}
@@ -8,9 +8,13 @@ use cosmwasm_std::{
     Storage, 
     StdResult,
     Timestamp,
+    Uint128,
+    Empty,
+    Coin,
     Order
 };
 use cw_storage_plus::{
+  Bound,
   Item, 
   MultiIndex,
   IndexList, 
@@ -23,16 +27,78 @@ use cw_storage_plus::{
   Prefixer
 };
 
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::confusable_detection::skeleton;
+
 use crate::traits::Donation;
 use crate::error::ContractError;
 
 pub struct AlpineContract<'a> {
+    // The owner holds every role, and can grant and revoke the others
+    pub owner: Item<'a, Addr>,
+    // An owner nominated by the current owner, who hasn't accepted ownership yet
+    pub pending_owner: Item<'a, Addr>,
+    // Maps a role name and an address to a marker when the address holds the role
+    pub roles: Map<'a, (&'a str, &'a Addr), Empty>,
+    // Fee configuration set at instantiation
+    pub config: Item<'a, Config>,
+    // Operations which are currently paused. Nothing is paused if this hasn't been saved
+    pub pause_state: Item<'a, PauseState>,
+    // Maps the denoms which can be donated to their display data and minimum donation
+    pub accepted_denoms: Map<'a, &'a str, AcceptedDenom>,
+    // Marks the CW721 contracts whose NFTs can be gifted, keyed by the contract address
+    pub accepted_nft_contracts: Map<'a, &'a Addr, Empty>,
+    // A fee change which takes effect once its notice period has passed
+    pub pending_fee_change: Item<'a, PendingFeeChange>,
     pub donation_count: Item<'a, u64>,
-    pub donations: IndexedMap<'a, &'a str, DonationInfo, DonationIndexes<'a>>,
-    // Create a data structure which maps registered usernames to user objects
+    // Donations keyed by their ID, so that ranges are in the order donations were sent
+    pub donations: IndexedMap<'a, u64, DonationInfo, DonationIndexes<'a>>,
+    // Create a data structure which maps registered usernames to user objects. Keys are the lowercased username,
+    // and the user object keeps the casing the username was registered with
     pub usernames: Map<'a, String, AlpineUser>,
     // Create a data structure which maps registered addresses to user objects
-    pub addresses: Map<'a, Addr, AlpineUser>
+    pub addresses: Map<'a, Addr, AlpineUser>,
+    // Running totals of what each user has sent and received, keyed by their address and the denom
+    pub user_totals: Map<'a, (&'a Addr, &'a str), UserTotals>,
+    // Marks each donor a recipient has received from, keyed by the recipient address and then the donor address
+    pub donors: Map<'a, (&'a Addr, &'a Addr), Empty>,
+    // The number of different donors each recipient has received from
+    pub unique_donors: Map<'a, &'a Addr, u64>,
+    // The largest donation each recipient has received, keyed by their address and the denom
+    pub largest_donations: Map<'a, (&'a Addr, &'a str), LargestDonation>,
+    // Running totals of every donation and fee, keyed by the denom
+    pub platform_totals: Map<'a, &'a str, PlatformTotals>,
+    // What each donor has given a recipient, keyed by the recipient address, the denom and the donor address
+    pub supporter_totals: Map<'a, (&'a Addr, &'a str, &'a Addr), Uint128>,
+    // Ranks each recipient's donors, keyed by the recipient address, the denom and a rank key which sorts by amount
+    pub supporter_ranks: Map<'a, (&'a Addr, &'a str, Vec<u8>), Supporter>,
+    // Donation statistics for each recipient, keyed by their address and the day
+    pub creator_daily_stats: Map<'a, (&'a Addr, u64), DailyStats>,
+    // Donation statistics for the whole platform, keyed by the day
+    pub platform_daily_stats: Map<'a, u64, DailyStats>,
+    // Every username change, keyed by the address of the user and the time of the change in nanoseconds
+    pub username_history: Map<'a, (&'a Addr, u64), UsernameChange>,
+    // Usernames given up by a rename, keyed by the lowercased username. Only their previous owner can take them until
+    // the grace period is over
+    pub released_usernames: Map<'a, String, ReleasedUsername>,
+    // Usernames listed for sale, keyed by the lowercased username
+    pub username_listings: Map<'a, String, UsernameListing>,
+    // Usernames offered to another address, waiting for it to accept. Keyed by the lowercased username
+    pub username_transfers: Map<'a, String, UsernameTransfer>,
+    // Usernames which only a verifier can allocate, keyed by the lowercased username
+    pub reserved_usernames: Map<'a, String, Empty>,
+    // Patterns usernames can't match, keyed by the kind of pattern and the lowercased pattern
+    pub blocked_patterns: Map<'a, (&'a str, &'a str), BlockedPattern>,
+    // Indexes every lowercased username under its confusable skeleton, keyed by the skeleton and the username
+    pub username_skeletons: Map<'a, (&'a str, &'a str), Empty>,
+    // Indexes released usernames under their confusable skeleton, keyed by the skeleton and the lowercased username
+    pub released_skeletons: Map<'a, (&'a str, &'a str), Empty>,
+    // Indexes reserved usernames under their confusable skeleton, keyed by the skeleton and the lowercased username
+    pub reserved_skeletons: Map<'a, (&'a str, &'a str), Empty>,
+    // When each username expires, keyed by the lowercased username. Usernames without an expiry never expire
+    pub username_expiries: Map<'a, String, Timestamp>,
+    // The annual renewal fee of usernames, keyed by the longest username length the fee applies to
+    pub renewal_fees: Map<'a, u32, Coin>
 }
 
 impl<'a> Donation for AlpineContract<'a> { }
@@ -41,7 +107,7 @@ impl Default for AlpineContract<'static> {
     fn default() -> Self {
         Self::new(
             "num_donations",
-            "donations",
+            "donation_records",
             "usernames",
             "addresses"
         )
@@ -57,14 +123,45 @@ impl<'a> AlpineContract<'a> {
         addresses: &'a str
     ) -> Self {
         let indexes = DonationIndexes {
-            sender: MultiIndex::new(|d| d.sender.clone(), donations, "donations__sender"),
-            recipient: MultiIndex::new(|d| d.recipient.clone(), donations, "donations__recipient"),
+            sender: MultiIndex::new(donation_sender_idx, donations, "donation_records__sender"),
+            recipient: MultiIndex::new(donation_recipient_idx, donations, "donation_records__recipient"),
+            pair: MultiIndex::new(donation_pair_idx, donations, "donation_records__pair"),
+            sender_time: MultiIndex::new(donation_sender_time_idx, donations, "donation_records__sender_time"),
+            recipient_time: MultiIndex::new(donation_recipient_time_idx, donations, "donation_records__recipient_time"),
         };
         Self {
+            owner: Item::new("owner"),
+            pending_owner: Item::new("pending_owner"),
+            roles: Map::new("roles"),
+            config: Item::new("config"),
+            pause_state: Item::new("pause_state"),
+            accepted_denoms: Map::new("accepted_denoms"),
+            accepted_nft_contracts: Map::new("accepted_nft_contracts"),
+            pending_fee_change: Item::new("pending_fee_change"),
             donation_count: Item::new(donation_count_key),
             donations: IndexedMap::new(donations, indexes),
             usernames: Map::new(usernames),
-            addresses: Map::new(addresses)
+            addresses: Map::new(addresses),
+            user_totals: Map::new("user_totals"),
+            donors: Map::new("donors"),
+            unique_donors: Map::new("unique_donors"),
+            largest_donations: Map::new("largest_donations"),
+            platform_totals: Map::new("platform_totals"),
+            supporter_totals: Map::new("supporter_totals"),
+            supporter_ranks: Map::new("supporter_ranks"),
+            creator_daily_stats: Map::new("creator_daily_stats"),
+            platform_daily_stats: Map::new("platform_daily_stats"),
+            username_history: Map::new("username_history"),
+            released_usernames: Map::new("released_usernames"),
+            username_listings: Map::new("username_listings"),
+            username_transfers: Map::new("username_transfers"),
+            reserved_usernames: Map::new("reserved_usernames"),
+            blocked_patterns: Map::new("blocked_patterns"),
+            username_skeletons: Map::new("username_skeletons"),
+            released_skeletons: Map::new("released_skeletons"),
+            reserved_skeletons: Map::new("reserved_skeletons"),
+            username_expiries: Map::new("username_expiries"),
+            renewal_fees: Map::new("renewal_fees")
         }
     }
 
@@ -80,43 +177,234 @@ impl<'a> AlpineContract<'a> {
         Ok(val)
     }
 
-    // Return an AlpineUser from a query with the username
-    pub fn find_alpine_username(&self, storage: &dyn Storage, username: String) -> Result<AlpineUser, ContractError> {
-        let mut usernames = self.usernames.keys(
-            storage,
-            None,
-            None,
-            Order::Descending
-        );
-        // Iterate through the alpine users and check if any of the usernames match the queried username
-        // The intention here is to pull an Alpine user regardless of the character case when sending/querying donations
-        let found = match (*usernames).into_iter().filter(
-            |u| u.as_deref().unwrap().to_lowercase() == username.to_lowercase()).next() {
-                Some(user) => user?,
-                None => String::from("")
-            };
-
-        // Pull the Alpine user for the designated username
-        let alpine_user = match self.usernames.may_load(storage, found.clone())? {
-            Some(user) => user,
-            None => return Err(ContractError::UserNotFound { user: username })
-        };
+    // Return an AlpineUser from a query with the username, regardless of the character case. Usernames which have
+    // expired at the given time aren't found
+    pub fn find_alpine_username(&self, storage: &dyn Storage, username: String, time: Timestamp) -> Result<AlpineUser, ContractError> {
+        let key = username_key(&username);
+        if self.username_expired(storage, &key, time)? {
+            return Err(ContractError::UserNotFound { user: username })
+        }
+        match self.usernames.may_load(storage, key)? {
+            Some(user) => Ok(user),
+            None => Err(ContractError::UserNotFound { user: username })
+        }
+    }
 
-        Ok(alpine_user)
+    // Check whether a lowercased username has expired at the given time
+    pub fn username_expired(&self, storage: &dyn Storage, key: &str, time: Timestamp) -> StdResult<bool> {
+        Ok(self.username_expiries.may_load(storage, key.to_string())?.map_or(false, |expires_at| time >= expires_at))
+    }
+
+    // Check whether a lowercased username has expired and its grace period is over, so that anybody can take it
+    pub fn username_lapsed(&self, storage: &dyn Storage, key: &str, time: Timestamp) -> StdResult<bool> {
+        Ok(self.username_expiries
+            .may_load(storage, key.to_string())?
+            .map_or(false, |expires_at| time >= expires_at.plus_seconds(EXPIRED_USERNAME_GRACE_PERIOD)))
+    }
+
+    // Return the annual renewal fee of a username. Each fee applies to usernames up to its length, and the fee with
+    // the shortest length that covers the username is charged. Usernames longer than every length can't be renewed
+    pub fn renewal_fee(&self, storage: &dyn Storage, username: &str) -> StdResult<Option<Coin>> {
+        let length = username.chars().count() as u32;
+        self.renewal_fees
+            .range(storage, Some(Bound::inclusive(length)), None, Order::Ascending)
+            .next()
+            .transpose()
+            .map(|fee| fee.map(|(_, fee)| fee))
+    }
+
+    // Check whether an address can take a username at the given time, returning why it can't. A username can't be
+    // taken while another user holds it, unless it expired and its grace period is over, while it's within the grace
+    // period after another user renamed away from it, or while it's reserved or matches a blocked pattern. Passing no
+    // address checks whether anybody can take it. A username which looks like one that another user holds or
+    // released, or one that's reserved or blocked, is rejected for the same reason unless both are ASCII while Unicode
+    // usernames are disabled
+    pub fn username_unavailable_reason(
+        &self,
+        storage: &dyn Storage,
+        username: &str,
+        address: Option<&Addr>,
+        time: Timestamp
+    ) -> StdResult<Option<UnavailableReason>> {
+        let key = username_key(username);
+        if let Some(user) = self.usernames.may_load(storage, key.clone())? {
+            if Some(&user.address) == address {
+                return Ok(None)
+            }
+            if !self.username_lapsed(storage, &key, time)? {
+                let reason = match self.username_expiries.may_load(storage, key.clone())? {
+                    Some(expires_at) if time >= expires_at => UnavailableReason::Expired {
+                        available_at: expires_at.plus_seconds(EXPIRED_USERNAME_GRACE_PERIOD)
+                    },
+                    _ => UnavailableReason::Taken
+                };
+                return Ok(Some(reason))
+            }
+        }
+        if let Some(released) = self.released_usernames.may_load(storage, key.clone())? {
+            if time < released.available_at && Some(&released.address) != address {
+                return Ok(Some(UnavailableReason::GracePeriod { available_at: released.available_at }))
+            }
+        }
+        if self.reserved_usernames.has(storage, key.clone()) {
+            return Ok(Some(UnavailableReason::Reserved))
+        }
+        let unicode = self.unicode_usernames(storage)?;
+        let skeleton = username_skeleton(username);
+        for existing in self.username_skeletons.prefix(&skeleton).keys(storage, None, None, Order::Ascending) {
+            let existing = existing?;
+            if existing == key {
+                continue
+            }
+            if let Some(user) = self.usernames.may_load(storage, existing.clone())? {
+                if !compare_lookalikes(unicode, username, &user.username) {
+                    continue
+                }
+                if Some(&user.address) != address && !self.username_lapsed(storage, &existing, time)? {
+                    return Ok(Some(UnavailableReason::Confusable { username: user.username }))
+                }
+            }
+        }
+        for existing in self.released_skeletons.prefix(&skeleton).keys(storage, None, None, Order::Ascending) {
+            let existing = existing?;
+            if existing == key || !compare_lookalikes(unicode, username, &existing) {
+                continue
+            }
+            if let Some(released) = self.released_usernames.may_load(storage, existing)? {
+                if time < released.available_at && Some(&released.address) != address {
+                    return Ok(Some(UnavailableReason::GracePeriod { available_at: released.available_at }))
+                }
+            }
+        }
+        for existing in self.reserved_skeletons.prefix(&skeleton).keys(storage, None, None, Order::Ascending) {
+            let existing = existing?;
+            if existing != key && compare_lookalikes(unicode, username, &existing) {
+                return Ok(Some(UnavailableReason::Reserved))
+            }
+        }
+        for pattern in self.blocked_patterns.range(storage, None, None, Order::Ascending) {
+            let (_, pattern) = pattern?;
+            if pattern.matches(&key) || (compare_lookalikes(unicode, username, &pattern.pattern) && pattern.matches_skeleton(&skeleton)) {
+                return Ok(Some(UnavailableReason::Blocked { pattern }))
+            }
+        }
+        Ok(None)
     }
 
-    // Check if a username is taken regardless of username casing
-    pub fn contains_username(&self, storage: &dyn Storage, username: String) -> bool {
-        let usernames: Vec<Result<std::string::String, cosmwasm_std::StdError>> = self.usernames.keys(
-            storage,
-            None,
-            None,
-            Order::Descending
-        ).into_iter().collect();
+    // Check whether usernames can use letters from any script
+    pub fn unicode_usernames(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.config.may_load(storage)?.map_or(false, |config| config.unicode_usernames))
+    }
+
+    // Save a user under their username and address, and index their username under its skeleton. Taking over a lapsed
+    // username leaves its previous holder unregistered, and cancels any listing or pending transfer they made
+    pub fn save_user(&self, storage: &mut dyn Storage, user: &AlpineUser) -> StdResult<()> {
+        let key = username_key(&user.username);
+        if let Some(previous) = self.usernames.may_load(storage, key.clone())? {
+            if previous.address != user.address {
+                if self.addresses.may_load(storage, previous.address.clone())?.map_or(false, |p| username_key(&p.username) == key) {
+                    self.addresses.remove(storage, previous.address);
+                }
+                self.username_listings.remove(storage, key.clone());
+                self.username_transfers.remove(storage, key.clone());
+            }
+        }
+        self.usernames.save(storage, key.clone(), user)?;
+        self.addresses.save(storage, user.address.clone(), user)?;
 
-        let search_result = usernames.iter().any(|u| u.as_ref().unwrap().to_lowercase() == username.to_lowercase());
+        self.username_skeletons.save(storage, (&username_skeleton(&user.username), &key), &Empty {})
+    }
+
+    // Reserve a lowercased username, indexing it under its skeleton
+    pub fn reserve_username(&self, storage: &mut dyn Storage, key: &str) -> StdResult<()> {
+        self.reserved_usernames.save(storage, key.to_string(), &Empty {})?;
+        self.reserved_skeletons.save(storage, (&username_skeleton(key), key), &Empty {})
+    }
 
-        search_result
+    // Stop reserving a lowercased username, along with its skeleton index entry
+    pub fn unreserve_username(&self, storage: &mut dyn Storage, key: &str) {
+        self.reserved_usernames.remove(storage, key.to_string());
+        self.reserved_skeletons.remove(storage, (&username_skeleton(key), key));
+    }
+
+    // Release a lowercased username for the grace period after a rename, indexing it under its skeleton
+    pub fn release_username(&self, storage: &mut dyn Storage, key: &str, released: &ReleasedUsername) -> StdResult<()> {
+        self.released_usernames.save(storage, key.to_string(), released)?;
+        self.released_skeletons.save(storage, (&username_skeleton(key), key), &Empty {})
+    }
+
+    // Forget that a lowercased username was released once somebody takes it, along with its skeleton index entry
+    pub fn clear_released_username(&self, storage: &mut dyn Storage, key: &str) {
+        self.released_usernames.remove(storage, key.to_string());
+        self.released_skeletons.remove(storage, (&username_skeleton(key), key));
+    }
+
+    // Remove a username along with its expiry and skeleton index entry
+    pub fn remove_username(&self, storage: &mut dyn Storage, username: &str) -> StdResult<()> {
+        let key = username_key(username);
+        self.usernames.remove(storage, key.clone());
+        self.username_expiries.remove(storage, key.clone());
+
+        self.username_skeletons.remove(storage, (&username_skeleton(username), &key));
+        Ok(())
+    }
+
+    // Return the most recent username change of an address, if it has ever changed its username
+    pub fn last_username_change(&self, storage: &dyn Storage, address: &Addr) -> StdResult<Option<UsernameChange>> {
+        self.username_history
+            .prefix(address)
+            .range(storage, None, None, Order::Descending)
+            .next()
+            .transpose()
+            .map(|change| change.map(|(_, change)| change))
+    }
+
+    // Return the config in force at the given time. A pending fee change applies once its effective time is reached
+    pub fn effective_config(&self, storage: &dyn Storage, time: Timestamp) -> StdResult<Config> {
+        let mut config = self.config.load(storage)?;
+        if let Some(pending) = self.pending_fee_change.may_load(storage)? {
+            if pending.is_effective(time) {
+                config.fee_bps = pending.fee_bps;
+                config.fee_collector = pending.fee_collector;
+            }
+        }
+        Ok(config)
+    }
+
+    // Write a pending fee change into the config once it has taken effect, returning the config in force
+    pub fn settle_fee_change(&self, storage: &mut dyn Storage, time: Timestamp) -> StdResult<Config> {
+        let config = self.effective_config(storage, time)?;
+        if let Some(pending) = self.pending_fee_change.may_load(storage)? {
+            if pending.is_effective(time) {
+                self.config.save(storage, &config)?;
+                self.pending_fee_change.remove(storage);
+            }
+        }
+        Ok(config)
+    }
+
+    // Verify that a coin is an accepted denom and isn't below the minimum donation for that denom
+    pub fn ensure_accepted_coin(&self, storage: &dyn Storage, coin: &Coin) -> Result<(), ContractError> {
+        let accepted_denom = match self.accepted_denoms.may_load(storage, &coin.denom)? {
+            Some(accepted_denom) => accepted_denom,
+            None => return Err(ContractError::DenomNotAccepted { denom: coin.denom.clone() })
+        };
+        if coin.amount < accepted_denom.min_amount {
+            return Err(ContractError::DonationBelowMinimum { denom: coin.denom.clone(), min_amount: accepted_denom.min_amount })
+        }
+        Ok(())
+    }
+
+    // Verify that an address holds a role. Every privileged execute goes through this guard.
+    // The owner passes every check, and nobody else passes a check for Role::Owner
+    pub fn ensure_role(&self, storage: &dyn Storage, address: &Addr, role: Role) -> Result<(), ContractError> {
+        if *address == self.owner.load(storage)? {
+            return Ok(())
+        }
+        if role != Role::Owner && self.roles.has(storage, (role.as_str(), address)) {
+            return Ok(())
+        }
+        Err(ContractError::Unauthorized {})
     }
 
     // Get an Alpine user by their wallet address
@@ -128,6 +416,392 @@ impl<'a> AlpineContract<'a> {
 
         Ok(alpine_user)
     }
+
+    // Add a donation to the running totals. Every coin is added with the fee taken from it
+    pub fn record_totals(
+        &self,
+        storage: &mut dyn Storage,
+        id: u64,
+        donation: &DonationInfo,
+        fees: &[Coin]
+    ) -> StdResult<()> {
+        for gross in donation.amount.iter() {
+            let denom = gross.denom.as_str();
+            let fee = fees.iter().find(|f| f.denom == gross.denom).map_or(Uint128::zero(), |f| f.amount);
+
+            self.user_totals.update(storage, (&donation.sender, denom), |totals| -> StdResult<_> {
+                let mut totals = totals.unwrap_or_else(|| UserTotals::new(denom));
+                totals.sent += gross.amount;
+                Ok(totals)
+            })?;
+            self.user_totals.update(storage, (&donation.recipient, denom), |totals| -> StdResult<_> {
+                let mut totals = totals.unwrap_or_else(|| UserTotals::new(denom));
+                totals.received += gross.amount;
+                Ok(totals)
+            })?;
+
+            let largest = self.largest_donations.may_load(storage, (&donation.recipient, denom))?;
+            if largest.map_or(true, |largest| gross.amount > largest.amount.amount) {
+                let largest = LargestDonation { id, amount: gross.clone() };
+                self.largest_donations.save(storage, (&donation.recipient, denom), &largest)?;
+            }
+
+            self.platform_totals.update(storage, denom, |totals| -> StdResult<_> {
+                let mut totals = totals.unwrap_or_else(|| PlatformTotals::new(denom));
+                totals.volume += gross.amount;
+                totals.fees += fee;
+                totals.donation_count += 1;
+                Ok(totals)
+            })?;
+
+            // Donations to yourself don't count towards your own leaderboard
+            if donation.sender != donation.recipient {
+                self.record_supporter(storage, &donation.recipient, &donation.sender, gross)?;
+            }
+        }
+
+        // Count the donor the first time they donate to this recipient
+        if !self.donors.has(storage, (&donation.recipient, &donation.sender)) {
+            self.donors.save(storage, (&donation.recipient, &donation.sender), &Empty {})?;
+            let count = self.unique_donors.may_load(storage, &donation.recipient)?.unwrap_or_default();
+            self.unique_donors.save(storage, &donation.recipient, &(count + 1))?;
+        }
+
+        // Add the donation to the recipient's and the platform's statistics for the day it was sent
+        let day = day_of(donation.timestamp.unwrap_or_default());
+        let add_to_stats = |stats: Option<DailyStats>| -> StdResult<DailyStats> {
+            let mut stats = stats.unwrap_or_else(|| DailyStats::new(day));
+            stats.add(donation, fees);
+            Ok(stats)
+        };
+        self.creator_daily_stats.update(storage, (&donation.recipient, day), add_to_stats)?;
+        self.platform_daily_stats.update(storage, day, add_to_stats)?;
+
+        Ok(())
+    }
+
+    // Add to what a donor has given a recipient, moving the donor to their new place on the recipient's leaderboard
+    fn record_supporter(&self, storage: &mut dyn Storage, recipient: &Addr, donor: &Addr, gross: &Coin) -> StdResult<()> {
+        let denom = gross.denom.as_str();
+        let previous = self.supporter_totals.may_load(storage, (recipient, denom, donor))?;
+        if let Some(previous) = previous {
+            self.supporter_ranks.remove(storage, (recipient, denom, supporter_rank_key(previous, donor)));
+        }
+
+        let amount = previous.unwrap_or_default() + gross.amount;
+        self.supporter_totals.save(storage, (recipient, denom, donor), &amount)?;
+        let supporter = Supporter { address: donor.clone(), amount };
+        self.supporter_ranks.save(storage, (recipient, denom, supporter_rank_key(amount, donor)), &supporter)
+    }
+
+    // Return the user currently registered to an address, or an unregistered user if there isn't one
+    pub fn resolve_user(&self, storage: &dyn Storage, address: Addr) -> StdResult<AlpineUser> {
+        let alpine_user = match self.addresses.may_load(storage, address.clone())? {
+            Some(user) => user,
+            None => AlpineUser { username: String::from(""), address }
+        };
+
+        Ok(alpine_user)
+    }
+}
+
+// The largest fee that can ever be configured, expressed in basis points (100%)
+pub const MAX_BPS: u64 = 10_000;
+// The shortest notice period a deployment can have, so that fee changes are always announced in advance
+pub const MIN_FEE_NOTICE_PERIOD: u64 = SECONDS_PER_DAY;
+
+// Define the fee configuration of a deployment. The fee is expressed in basis points and can
+// never be set above max_fee_bps. Fee changes only apply fee_notice_period seconds after they're proposed.
+// Both max_fee_bps and fee_notice_period are fixed when the contract is instantiated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub fee_bps: u64,
+    pub fee_collector: Addr,
+    pub max_fee_bps: u64,
+    pub fee_notice_period: u64,
+    // Whether usernames can use letters from any script rather than only ASCII letters
+    pub unicode_usernames: bool
+}
+
+impl Config {
+    // Verify that the fee respects the maximum-fee invariant, and that fee changes are given notice
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.max_fee_bps > MAX_BPS || self.fee_bps > self.max_fee_bps {
+            return Err(ContractError::InvalidFee { fee_bps: self.fee_bps, max_fee_bps: self.max_fee_bps })
+        }
+        if self.fee_notice_period < MIN_FEE_NOTICE_PERIOD {
+            return Err(ContractError::InvalidFeeNoticePeriod {
+                fee_notice_period: self.fee_notice_period,
+                min_fee_notice_period: MIN_FEE_NOTICE_PERIOD
+            })
+        }
+        Ok(())
+    }
+
+    // Calculate the fee owed on a donation amount, rounding down
+    pub fn fee_for(&self, amount: Uint128) -> Uint128 {
+        amount.multiply_ratio(self.fee_bps, MAX_BPS)
+    }
+
+    // Split every coin of a donation into the fee and the recipient's share
+    pub fn split(&self, funds: &[Coin]) -> Vec<CoinSplit> {
+        split_funds(funds, self.fee_bps)
+    }
+}
+
+// Split every coin into the fee at a rate in basis points and the recipient's share. Fees round down
+pub fn split_funds(funds: &[Coin], fee_bps: u64) -> Vec<CoinSplit> {
+    funds.iter().map(|gross| {
+        let fee = gross.amount.multiply_ratio(fee_bps, MAX_BPS);
+        CoinSplit {
+            gross: gross.clone(),
+            fee: Coin { denom: gross.denom.clone(), amount: fee },
+            net: Coin { denom: gross.denom.clone(), amount: gross.amount - fee }
+        }
+    }).collect()
+}
+
+// A donated coin split into the fee and what the recipient receives
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CoinSplit {
+    pub gross: Coin,
+    pub fee: Coin,
+    pub net: Coin
+}
+
+// Define the roles which gate privileged executes. Only the owner can grant roles, and Role::Owner can't be granted
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Owner,
+    FeeManager,
+    Moderator,
+    Verifier,
+    Pauser
+}
+
+impl Role {
+    // The name of the role, which is also its storage key
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::FeeManager => "fee_manager",
+            Role::Moderator => "moderator",
+            Role::Verifier => "verifier",
+            Role::Pauser => "pauser"
+        }
+    }
+}
+
+// Define a denom which can be donated, along with the data the frontend needs to display it.
+// Donations below min_amount are rejected. CW20 tokens are accepted by using their contract address as the denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AcceptedDenom {
+    pub denom: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub min_amount: Uint128
+}
+
+// Running totals of what a user has sent and received in one denom. Amounts are before fees
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserTotals {
+    pub denom: String,
+    pub sent: Uint128,
+    pub received: Uint128
+}
+
+impl UserTotals {
+    pub fn new(denom: &str) -> UserTotals {
+        UserTotals { denom: denom.to_string(), sent: Uint128::zero(), received: Uint128::zero() }
+    }
+}
+
+// A donor's place on a recipient's leaderboard, with everything they've given the recipient in one denom before fees
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Supporter {
+    pub address: Addr,
+    pub amount: Uint128
+}
+
+// The largest donation a user has received in one denom, and its ID. Amounts are before fees
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LargestDonation {
+    pub id: u64,
+    pub amount: Coin
+}
+
+// The donations sent on one day. Days are counted from the Unix epoch, and the volume is before fees. NFT gifts have
+// no volume, so they're counted apart from donations of funds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DailyStats {
+    pub day: u64,
+    pub count: u64,
+    pub gift_count: u64,
+    pub volume: Vec<Coin>,
+    pub fees: Vec<Coin>
+}
+
+impl DailyStats {
+    pub fn new(day: u64) -> DailyStats {
+        DailyStats { day, count: 0, gift_count: 0, volume: vec![], fees: vec![] }
+    }
+
+    // Add a donation and the fees taken from it
+    pub fn add(&mut self, donation: &DonationInfo, fees: &[Coin]) {
+        match donation.token_type {
+            TokenType::Cw721 { .. } => self.gift_count += 1,
+            _ => self.count += 1
+        }
+        for gross in donation.amount.iter() {
+            add_coin(&mut self.volume, gross);
+        }
+        for fee in fees.iter() {
+            add_coin(&mut self.fees, fee);
+        }
+    }
+}
+
+// Add a coin to a list of coins, merging it into the coin of the same denom if there is one
+fn add_coin(coins: &mut Vec<Coin>, amount: &Coin) {
+    match coins.iter_mut().find(|c| c.denom == amount.denom) {
+        Some(existing) => existing.amount += amount.amount,
+        None => coins.push(amount.clone())
+    }
+}
+
+// Running totals of every donation in one denom. The volume is before fees
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlatformTotals {
+    pub denom: String,
+    pub volume: Uint128,
+    pub fees: Uint128,
+    pub donation_count: u64
+}
+
+impl PlatformTotals {
+    pub fn new(denom: &str) -> PlatformTotals {
+        PlatformTotals { denom: denom.to_string(), volume: Uint128::zero(), fees: Uint128::zero(), donation_count: 0 }
+    }
+}
+
+// The number of seconds a user has to wait between username changes
+pub const USERNAME_CHANGE_COOLDOWN: u64 = 30 * SECONDS_PER_DAY;
+// The number of seconds a username stays reserved for its previous owner after they rename away from it
+pub const RELEASED_USERNAME_GRACE_PERIOD: u64 = 90 * SECONDS_PER_DAY;
+// The number of seconds a registration or renewal keeps a username
+pub const USERNAME_REGISTRATION_PERIOD: u64 = 365 * SECONDS_PER_DAY;
+// The number of seconds after a username expires that only its holder can renew it
+pub const EXPIRED_USERNAME_GRACE_PERIOD: u64 = 30 * SECONDS_PER_DAY;
+
+// A username change, with the username before and after it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UsernameChange {
+    pub old_username: String,
+    pub new_username: String,
+    pub changed_at: Timestamp
+}
+
+// A username which was renamed away from, and when anybody other than its previous owner can take it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReleasedUsername {
+    pub address: Addr,
+    pub available_at: Timestamp
+}
+
+// A username listed for sale by its holder at a fixed price. The platform fee is taken from the price when it sells
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UsernameListing {
+    pub username: String,
+    pub seller: Addr,
+    pub price: Coin
+}
+
+// A username its holder has offered to another address for free
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UsernameTransfer {
+    pub username: String,
+    pub from: Addr,
+    pub to: Addr
+}
+
+// Define where a blocked pattern has to appear in a username to block it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    Prefix,
+    Suffix,
+    Substring
+}
+
+impl PatternKind {
+    // The name of the kind of pattern, which is also its storage key
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatternKind::Prefix => "prefix",
+            PatternKind::Suffix => "suffix",
+            PatternKind::Substring => "substring"
+        }
+    }
+}
+
+// A pattern which usernames can't match. Patterns are compared against the lowercased username
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockedPattern {
+    pub kind: PatternKind,
+    pub pattern: String
+}
+
+impl BlockedPattern {
+    // Check whether a lowercased username matches the pattern
+    pub fn matches(&self, key: &str) -> bool {
+        match self.kind {
+            PatternKind::Prefix => key.starts_with(&self.pattern),
+            PatternKind::Suffix => key.ends_with(&self.pattern),
+            PatternKind::Substring => key.contains(&self.pattern)
+        }
+    }
+
+    // Check whether the skeleton of a username matches the skeleton of the pattern, so that lookalikes are blocked too
+    pub fn matches_skeleton(&self, skeleton: &str) -> bool {
+        BlockedPattern { kind: self.kind, pattern: username_skeleton(&self.pattern) }.matches(skeleton)
+    }
+}
+
+// Define why a username can't be taken
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnavailableReason {
+    Invalid { reason: String },
+    Taken,
+    Expired { available_at: Timestamp },
+    GracePeriod { available_at: Timestamp },
+    Reserved,
+    Blocked { pattern: BlockedPattern },
+    Confusable { username: String }
+}
+
+// Define which operations are paused. Paused operations are rejected until they're unpaused, while queries keep working
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PauseState {
+    pub donations: bool,
+    pub registrations: bool,
+    pub profile_updates: bool
+}
+
+// Define a proposed fee and fee collector, which replace the current ones at effective_at
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingFeeChange {
+    pub fee_bps: u64,
+    pub fee_collector: Addr,
+    pub effective_at: Timestamp
+}
+
+impl PendingFeeChange {
+    // Check whether the change is in force at the given time
+    pub fn is_effective(&self, time: Timestamp) -> bool {
+        time >= self.effective_at
+    }
 }
 
 // Define an Alpine user as a username and wallet address
@@ -145,11 +819,8 @@ impl AlpineUser {
             Err(_) => return Err(ContractError::InvalidWalletAddress { address: address.to_string() })
         };
         
-        let username = match username {
-            Some(name) => name,
-            None => String::from("")
-        };
-        
+        let username = username.unwrap_or_default();
+
         Ok(AlpineUser { username, address })
     }
 
@@ -174,42 +845,147 @@ impl<'a> PrimaryKey<'a> for AlpineUser {
     type Suffix = &'a AlpineUser;
     type SuperSuffix = &'a AlpineUser;
 
-    fn key(&self) -> Vec<Key> {
+    fn key(&self) -> Vec<Key<'_>> {
         vec![Key::Ref(self.address.as_bytes())]
     }
 }
 
 impl<'a> Prefixer<'a> for AlpineUser {
-    fn prefix(&self) -> Vec<Key> {
+    fn prefix(&self) -> Vec<Key<'_>> {
         vec![Key::Ref(self.address.as_bytes())]
     }
 }
 
+// A donation refers to its sender and recipient by address. Their usernames at the time of the donation are kept
+// as a snapshot, and their current usernames are looked up when donations are returned. The amount is the gross
+// funds, and the splits record the fee and net amount of every coin at the fee rate in force when it was sent
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct DonationInfo {
-    pub sender: AlpineUser,
-    pub recipient: AlpineUser,
+    pub sender: Addr,
+    pub sender_username: String,
+    pub recipient: Addr,
+    pub recipient_username: String,
     pub amount: Vec<cosmwasm_std::Coin>,
     pub message: String,
-    pub timestamp: Option<Timestamp>
+    pub timestamp: Option<Timestamp>,
+    pub token_type: TokenType,
+    pub splits: Vec<CoinSplit>,
+    pub fee_bps: u64
+}
+
+impl DonationInfo {
+    // Create a donation between two users, taking a snapshot of their usernames
+    pub fn new(
+        sender: AlpineUser,
+        recipient: AlpineUser,
+        amount: Vec<Coin>,
+        message: String,
+        timestamp: Timestamp,
+        token_type: TokenType
+    ) -> DonationInfo {
+        DonationInfo {
+            sender: sender.address,
+            sender_username: sender.username,
+            recipient: recipient.address,
+            recipient_username: recipient.username,
+            amount,
+            message,
+            timestamp: Some(timestamp),
+            token_type,
+            splits: vec![],
+            fee_bps: 0
+        }
+    }
+
+    // Record the fee rate which applies to the donation and split its funds at that rate
+    pub fn apply_fee(&mut self, fee_bps: u64) {
+        self.splits = split_funds(&self.amount, fee_bps);
+        self.fee_bps = fee_bps;
+    }
+
+    // The time the donation was sent in nanoseconds, which orders the time indexes. Donations without a timestamp sort first
+    pub fn time_key(&self) -> u64 {
+        self.timestamp.map_or(0, |t| t.nanos())
+    }
+}
+
+// Define the kind of token a donation was made in. The amount of a CW20 donation is denominated in the token contract address.
+// NFT gifts have no amount, and record the NFT contract and token ID instead
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    Native,
+    Cw20 { contract_addr: Addr },
+    Cw721 { contract_addr: Addr, token_id: String }
 }
 
 // Creates a couple of indexes that we can use to search our indexed map
 pub struct DonationIndexes<'a>{
     // Allows search results with multiple values for sender/receiver (S/R).
-    // A S/R has an index of the S/R's address and the ID of the donation as bytes
-    pub sender: MultiIndex<'a, AlpineUser, DonationInfo, Vec<u8>>,
-    pub recipient: MultiIndex<'a, AlpineUser, DonationInfo, Vec<u8>>,
+    // A S/R has an index of the S/R's address and the ID of the donation
+    pub sender: MultiIndex<'a, Addr, DonationInfo, u64>,
+    pub recipient: MultiIndex<'a, Addr, DonationInfo, u64>,
+    // Indexes donations by the sender and recipient addresses together, for what one user has given another
+    pub pair: MultiIndex<'a, (Addr, Addr), DonationInfo, u64>,
+    // Index donations by the S/R address and the time they were sent, so that time ranges only scan matching donations
+    pub sender_time: MultiIndex<'a, (Addr, u64), DonationInfo, u64>,
+    pub recipient_time: MultiIndex<'a, (Addr, u64), DonationInfo, u64>,
 }
 
 // Boilerplate code which builds a list of indexes
 impl<'a> IndexList<DonationInfo> for DonationIndexes<'a> {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item=&'_ dyn Index<DonationInfo>> + '_> {
-      let v: Vec<&dyn Index<DonationInfo>> = vec![&self.sender, &self.recipient];
+      let v: Vec<&dyn Index<DonationInfo>> = vec![
+          &self.sender,
+          &self.recipient,
+          &self.pair,
+          &self.sender_time,
+          &self.recipient_time
+      ];
       Box::new(v.into_iter())
     }
 }
 
-pub fn donation_sender_idx(d: &DonationInfo) -> AlpineUser { d.sender.clone() }
+// Return the key a username is stored under in the usernames map, so that lookups ignore character case and
+// compatibility forms of characters. ASCII usernames are only lowercased
+pub fn username_key(username: &str) -> String {
+    username.nfkc().collect::<String>().to_lowercase()
+}
+
+// Check whether a username has to be compared with another one which has the same skeleton. ASCII usernames are only
+// compared with each other while Unicode usernames are enabled, so turning them off keeps Unicode ones protected
+pub fn compare_lookalikes(unicode: bool, username: &str, other: &str) -> bool {
+    unicode || !username.is_ascii() || !other.is_ascii()
+}
+
+// Return the confusable skeleton of a username. Usernames which look alike, such as ones using Latin and Cyrillic
+// letters which share a shape, have the same skeleton
+pub fn username_skeleton(username: &str) -> String {
+    skeleton(&username_key(username)).collect()
+}
+
+// The number of seconds in a day, which is the length of a statistics bucket
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Return the day a time falls on, counted from the Unix epoch
+pub fn day_of(time: Timestamp) -> u64 {
+    time.seconds() / SECONDS_PER_DAY
+}
+
+// Return the key a supporter is ranked under. The big-endian amount comes first, so ranges over a leaderboard are ordered
+// by amount, and the address keeps donors with equal amounts apart
+pub fn supporter_rank_key(amount: Uint128, donor: &Addr) -> Vec<u8> {
+    let mut key = amount.u128().to_be_bytes().to_vec();
+    key.extend_from_slice(donor.as_bytes());
+    key
+}
+
+pub fn donation_sender_idx(d: &DonationInfo) -> Addr { d.sender.clone() }
+
+pub fn donation_recipient_idx(d: &DonationInfo) -> Addr { d.recipient.clone() }
+
+pub fn donation_pair_idx(d: &DonationInfo) -> (Addr, Addr) { (d.sender.clone(), d.recipient.clone()) }
+
+pub fn donation_sender_time_idx(d: &DonationInfo) -> (Addr, u64) { (d.sender.clone(), d.time_key()) }
 
-pub fn donation_recipient_idx(d: &DonationInfo) -> AlpineUser { d.recipient.clone() }
+pub fn donation_recipient_time_idx(d: &DonationInfo) -> (Addr, u64) { (d.recipient.clone(), d.time_key()) }
@@ -6,30 +6,138 @@ use cosmwasm_std::{
     MessageInfo,
     Response,  
     StdResult,
-    Addr
+    Addr,
+    Coin,
+    Timestamp
 };
 
 use crate::msg::{
     MultiDonationResponse, 
+    DonationResponse,
+    DonationFilter,
     DonationCountResponse,
     UsernameAvailableResponse,
     MultiUserResponse,
-    AlpineUserResponse
+    AlpineUserResponse,
+    UsernameHistoryResponse,
+    UsernameListingsResponse,
+    UsernameTransferResponse,
+    ReservedUsernamesResponse,
+    BlockedPatternsResponse,
+    UsernameExpiryResponse,
+    RenewalFeesResponse,
+    ConfigResponse,
+    PendingFeeChangeResponse,
+    OwnershipResponse,
+    RoleHoldersResponse,
+    PauseStateResponse,
+    AcceptedDenomsResponse,
+    AcceptedNftContractsResponse,
+    UserTotalsResponse,
+    UniqueDonorsResponse,
+    LargestDonationResponse,
+    PlatformTotalsResponse,
+    TopSupportersResponse,
+    DailyStatsResponse,
+    StatementResponse,
+    StatementCursor,
+    SimulateDonationResponse
 };
-use crate::state::AlpineUser;
+use crate::state::{AlpineUser, Role, AcceptedDenom, PatternKind};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
 
 use crate::ContractError;
 
 pub trait Donation: DonationQuery + DonationExecute { }
 
 pub trait DonationQuery {
-    fn get_sent_donations(&self, deps: Deps, sender: String) -> StdResult<MultiDonationResponse>;
-    fn get_received_donations(&self, deps: Deps, recipient: String) -> StdResult<MultiDonationResponse>;
+    fn get_sent_donations(
+        &self,
+        deps: Deps,
+        sender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse>;
+    fn get_received_donations(
+        &self,
+        deps: Deps,
+        recipient: String,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse>;
     fn get_donation_count(&self, deps: Deps) -> StdResult<DonationCountResponse>;
-    fn is_username_available(&self, deps: Deps, username: String) -> StdResult<UsernameAvailableResponse>;
-    fn get_all_users(&self, deps: Deps) -> StdResult<MultiUserResponse>;
+    fn get_donation(&self, deps: Deps, id: u64) -> StdResult<DonationResponse>;
+    fn get_donations_between(
+        &self,
+        deps: Deps,
+        sender: String,
+        recipient: String,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse>;
+    fn search_donations(
+        &self,
+        deps: Deps,
+        filter: DonationFilter,
+        start_after: Option<u64>,
+        limit: Option<u32>
+    ) -> StdResult<MultiDonationResponse>;
+    fn get_user_totals(&self, deps: Deps, username: String) -> StdResult<UserTotalsResponse>;
+    fn get_unique_donors(&self, deps: Deps, username: String) -> StdResult<UniqueDonorsResponse>;
+    fn get_largest_donation(&self, deps: Deps, username: String, denom: String) -> StdResult<LargestDonationResponse>;
+    fn get_platform_totals(&self, deps: Deps) -> StdResult<PlatformTotalsResponse>;
+    fn get_top_supporters(
+        &self,
+        deps: Deps,
+        recipient: String,
+        denom: String,
+        limit: Option<u32>
+    ) -> StdResult<TopSupportersResponse>;
+    fn get_daily_stats(
+        &self,
+        deps: Deps,
+        recipient: Option<String>,
+        from: Timestamp,
+        to: Timestamp,
+        limit: Option<u32>
+    ) -> StdResult<DailyStatsResponse>;
+    fn get_statement(
+        &self,
+        deps: Deps,
+        recipient: String,
+        from: Timestamp,
+        to: Timestamp,
+        start_after: Option<StatementCursor>,
+        limit: Option<u32>
+    ) -> StdResult<StatementResponse>;
+    fn simulate_donation(
+        &self,
+        deps: Deps,
+        env: Env,
+        sender: String,
+        recipient: String,
+        message: Option<String>,
+        funds: Vec<Coin>
+    ) -> StdResult<SimulateDonationResponse>;
+    fn is_username_available(&self, deps: Deps, env: Env, username: String) -> StdResult<UsernameAvailableResponse>;
+    fn get_all_users(&self, deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<MultiUserResponse>;
     fn get_user_by_addr(&self, deps: Deps, address: Addr) -> StdResult<AlpineUserResponse>;
-    fn get_user_by_name(&self, deps: Deps, username: String) -> StdResult<AlpineUserResponse>;
+    fn get_user_by_name(&self, deps: Deps, env: Env, username: String) -> StdResult<AlpineUserResponse>;
+    fn get_username_history(&self, deps: Deps, address: Addr) -> StdResult<UsernameHistoryResponse>;
+    fn get_username_listings(&self, deps: Deps, env: Env, start_after: Option<String>, limit: Option<u32>) -> StdResult<UsernameListingsResponse>;
+    fn get_username_transfer(&self, deps: Deps, username: String) -> StdResult<UsernameTransferResponse>;
+    fn get_reserved_usernames(&self, deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<ReservedUsernamesResponse>;
+    fn get_blocked_patterns(&self, deps: Deps) -> StdResult<BlockedPatternsResponse>;
+    fn get_username_expiry(&self, deps: Deps, env: Env, username: String) -> StdResult<UsernameExpiryResponse>;
+    fn get_renewal_fees(&self, deps: Deps) -> StdResult<RenewalFeesResponse>;
+    fn get_config(&self, deps: Deps, env: Env) -> StdResult<ConfigResponse>;
+    fn get_pending_fee_change(&self, deps: Deps, env: Env) -> StdResult<PendingFeeChangeResponse>;
+    fn get_ownership(&self, deps: Deps) -> StdResult<OwnershipResponse>;
+    fn get_role_holders(&self, deps: Deps, role: Role) -> StdResult<RoleHoldersResponse>;
+    fn get_pause_state(&self, deps: Deps) -> StdResult<PauseStateResponse>;
+    fn get_accepted_denoms(&self, deps: Deps) -> StdResult<AcceptedDenomsResponse>;
+    fn get_accepted_nft_contracts(&self, deps: Deps) -> StdResult<AcceptedNftContractsResponse>;
 }
 
 pub trait DonationExecute{
@@ -42,11 +150,201 @@ pub trait DonationExecute{
         recipient: String, 
         message: String
     ) -> Result<Response, ContractError>;
+    fn receive_cw20(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        cw20_msg: Cw20ReceiveMsg
+    ) -> Result<Response, ContractError>;
+    fn receive_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        nft_msg: Cw721ReceiveMsg
+    ) -> Result<Response, ContractError>;
     fn register_user(
         &self,
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         user: AlpineUser,
         username: String
     ) -> Result<Response, ContractError>;
+    fn change_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String
+    ) -> Result<Response, ContractError>;
+    fn list_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        price: Coin
+    ) -> Result<Response, ContractError>;
+    fn delist_username(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response, ContractError>;
+    fn buy_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String
+    ) -> Result<Response, ContractError>;
+    fn transfer_username(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        recipient: String
+    ) -> Result<Response, ContractError>;
+    fn cancel_username_transfer(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response, ContractError>;
+    fn accept_username_transfer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String
+    ) -> Result<Response, ContractError>;
+    fn renew_username(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response, ContractError>;
+    fn reserve_usernames(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        usernames: Vec<String>
+    ) -> Result<Response, ContractError>;
+    fn unreserve_usernames(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        usernames: Vec<String>
+    ) -> Result<Response, ContractError>;
+    fn block_pattern(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        kind: PatternKind,
+        pattern: String
+    ) -> Result<Response, ContractError>;
+    fn unblock_pattern(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        kind: PatternKind,
+        pattern: String
+    ) -> Result<Response, ContractError>;
+    fn allocate_reserved_name(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        username: String,
+        address: String
+    ) -> Result<Response, ContractError>;
+    fn set_unicode_usernames(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        enabled: bool
+    ) -> Result<Response, ContractError>;
+    fn set_renewal_fee(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        length: u32,
+        fee: Option<Coin>
+    ) -> Result<Response, ContractError>;
+    fn update_config(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        fee_bps: Option<u64>,
+        fee_collector: Option<String>
+    ) -> Result<Response, ContractError>;
+    fn recover_funds(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        amount: Option<Vec<Coin>>
+    ) -> Result<Response, ContractError>;
+    fn cancel_fee_change(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response, ContractError>;
+    fn propose_owner(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        new_owner: String
+    ) -> Result<Response, ContractError>;
+    fn accept_ownership(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response, ContractError>;
+    fn grant_role(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        role: Role,
+        address: String
+    ) -> Result<Response, ContractError>;
+    fn revoke_role(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        role: Role,
+        address: String
+    ) -> Result<Response, ContractError>;
+    fn set_paused(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        donations: Option<bool>,
+        registrations: Option<bool>,
+        profile_updates: Option<bool>
+    ) -> Result<Response, ContractError>;
+    fn set_accepted_denom(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        accepted_denom: AcceptedDenom
+    ) -> Result<Response, ContractError>;
+    fn remove_accepted_denom(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        denom: String
+    ) -> Result<Response, ContractError>;
+    fn set_accepted_nft_contract(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String
+    ) -> Result<Response, ContractError>;
+    fn remove_accepted_nft_contract(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String
+    ) -> Result<Response, ContractError>;
 }